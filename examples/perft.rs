@@ -1,47 +1,330 @@
-use shogi_core::ToUsi;
+use shogi_core::{Move, ToUsi};
+use shogi_usi_parser::FromUsi;
+use std::ops::AddAssign;
 use std::process;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
 use std::time::Instant;
 use yasai::Position;
 
+const USAGE: &str = "usage: perft <depth> [startpos | --sfen <sfen>] [moves <move>...] [--divide] [--no-bulk] [--hash <mb>] [--threads <n>] [--stats]";
+
+struct Options {
+    depth: usize,
+    position: Position,
+    divide: bool,
+    bulk: bool,
+    hash_mb: usize,
+    threads: usize,
+    stats: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut iter = args.iter();
+    let depth = iter
+        .next()
+        .and_then(|s| s.parse().ok())
+        .ok_or("invalid depth")?;
+    let mut options = Options {
+        depth,
+        position: Position::default(),
+        divide: false,
+        bulk: true,
+        hash_mb: 0,
+        threads: 1,
+        stats: false,
+    };
+    let mut position = Vec::new();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--divide" => options.divide = true,
+            "--no-bulk" => options.bulk = false,
+            "--stats" => options.stats = true,
+            "--hash" => {
+                options.hash_mb = iter
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or("invalid hash size")?
+            }
+            "--threads" => {
+                options.threads = iter
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|&n| n > 0)
+                    .ok_or("invalid number of threads")?
+            }
+            "--sfen" => position.push("sfen"),
+            s => position.push(s),
+        }
+    }
+    if options.stats {
+        if options.depth == 0 {
+            return Err(String::from("--stats requires depth of at least 1"));
+        }
+        if options.divide || !options.bulk || options.hash_mb > 0 {
+            return Err(String::from(
+                "--stats can't be used with --divide, --no-bulk or --hash",
+            ));
+        }
+    }
+    if !position.is_empty() {
+        let core = shogi_core::Position::from_usi(&position.join(" "))
+            .map_err(|e| format!("invalid position: {e:?}"))?;
+        options.position =
+            Position::from_core_position(&core).map_err(|e| format!("invalid position: {e}"))?;
+    }
+    Ok(options)
+}
+
 fn main() {
-    let args = std::env::args().collect::<Vec<_>>();
-    if let Some(depth) = args.get(1).and_then(|s| s.parse().ok()) {
-        let mut pos = Position::default();
-        assert_eq!(30, pos.legal_moves().len());
-        let now = Instant::now();
-        let total = perft(&mut pos, depth, true);
-        let duration = now.elapsed();
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            println!("{e}");
+            println!("{USAGE}");
+            process::exit(1);
+        }
+    };
+    if options.stats {
+        run_stats(&options);
+        return;
+    }
+    let table = (options.hash_mb > 0).then(|| Table::new(options.hash_mb));
+    let now = Instant::now();
+    let counts = perft_root(&options, table.as_ref());
+    let duration = now.elapsed();
+    if options.divide {
+        for (m, count) in &counts {
+            println!("{}: {count}", m.to_usi_owned());
+        }
         println!();
-        println!("Time duration: {:?}", duration);
-        println!(
-            "Searched: {total} nodes: {} nps",
-            (total as u128) * 1_000_000_000 / duration.as_nanos()
-        );
+    }
+    let total = if options.depth == 0 {
+        1
     } else {
-        println!("usage: perft <depth>");
-        process::exit(1);
+        counts.iter().map(|(_, count)| count).sum::<u64>()
+    };
+    println!("Time duration: {:?}", duration);
+    println!(
+        "Searched: {total} nodes: {} nps",
+        u128::from(total) * 1_000_000_000 / duration.as_nanos().max(1)
+    );
+}
+
+/// Counts the nodes under each root move, splitting the root moves across the threads.
+fn perft_root(options: &Options, table: Option<&Table>) -> Vec<(Move, u64)> {
+    if options.depth == 0 {
+        return Vec::new();
     }
+    let moves = options.position.legal_moves();
+    let counts = moves.iter().map(|_| AtomicU64::new(0)).collect::<Vec<_>>();
+    let next = AtomicUsize::new(0);
+    thread::scope(|s| {
+        for _ in 0..options.threads {
+            s.spawn(|| {
+                let mut pos = options.position.clone();
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&m) = moves.get(i) else {
+                        break;
+                    };
+                    pos.do_move(m);
+                    let count = perft(&mut pos, options.depth - 1, options.bulk, table);
+                    pos.undo_move(m);
+                    counts[i].store(count, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    moves
+        .iter()
+        .zip(counts)
+        .map(|(&m, count)| (m, count.into_inner()))
+        .collect()
 }
 
-fn perft(pos: &mut Position, depth: usize, is_root: bool) -> usize {
+fn perft(pos: &mut Position, depth: usize, bulk: bool, table: Option<&Table>) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if bulk && depth == 1 {
+        return pos.legal_moves().len() as u64;
+    }
+    if let Some(count) = table.and_then(|t| t.probe(pos.key(), depth)) {
+        return count;
+    }
     let mut ret = 0;
     for m in pos.legal_moves() {
-        let count = if depth <= 1 {
-            1
-        } else {
-            pos.do_move(m);
-            let ret = if depth == 2 {
-                pos.legal_moves().len()
-            } else {
-                perft(pos, depth - 1, false)
-            };
-            pos.undo_move(m);
-            ret
-        };
-        ret += count;
-        if is_root {
-            println!("{}: {count}", m.to_usi_owned());
-        }
+        pos.do_move(m);
+        ret += perft(pos, depth - 1, bulk, table);
+        pos.undo_move(m);
+    }
+    if let Some(t) = table {
+        t.store(pos.key(), depth, ret);
     }
     ret
 }
+
+/// Node count and depth stored for a key, kept as `key ^ data` and `data`.
+/// A slot torn by stores from two threads doesn't match either key when probed.
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Node counts of the subtrees for `--hash`, one per slot, always replaced by the latest store.
+///
+/// The data of a slot is the count shifted by 8 bits with the remaining depth in the low bits,
+/// since the same position is counted to different depths.
+struct Table {
+    slots: Vec<Slot>,
+}
+
+impl Table {
+    fn new(mb: usize) -> Self {
+        let mut slots = Vec::new();
+        slots.resize_with((mb << 20) / std::mem::size_of::<Slot>(), Slot::default);
+        Self { slots }
+    }
+    fn slot(&self, key: u64) -> &Slot {
+        &self.slots[((u128::from(key) * self.slots.len() as u128) >> 64) as usize]
+    }
+    fn probe(&self, key: u64, depth: usize) -> Option<u64> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        (slot.key.load(Ordering::Relaxed) ^ data == key && data & 0xff == depth as u64)
+            .then_some(data >> 8)
+    }
+    fn store(&self, key: u64, depth: usize, count: u64) {
+        let slot = self.slot(key);
+        let data = count << 8 | depth as u64;
+        slot.key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
+
+/// Breakdown of the moves at a depth.
+#[derive(Debug, Clone, Copy, Default)]
+struct Stats {
+    nodes: u64,
+    captures: u64,
+    promotions: u64,
+    drops: u64,
+    checks: u64,
+    discovered_checks: u64,
+    double_checks: u64,
+    evasions: u64,
+    checkmates: u64,
+}
+
+impl AddAssign for Stats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.nodes += rhs.nodes;
+        self.captures += rhs.captures;
+        self.promotions += rhs.promotions;
+        self.drops += rhs.drops;
+        self.checks += rhs.checks;
+        self.discovered_checks += rhs.discovered_checks;
+        self.double_checks += rhs.double_checks;
+        self.evasions += rhs.evasions;
+        self.checkmates += rhs.checkmates;
+    }
+}
+
+/// Prints the breakdown for each depth. Every leaf is visited, without bulk counting and hashing.
+fn run_stats(options: &Options) {
+    let moves = options.position.legal_moves();
+    let total = Mutex::new(vec![Stats::default(); options.depth]);
+    let next = AtomicUsize::new(0);
+    let now = Instant::now();
+    thread::scope(|s| {
+        for _ in 0..options.threads {
+            s.spawn(|| {
+                let mut pos = options.position.clone();
+                let mut stats = vec![Stats::default(); options.depth];
+                while let Some(&m) = moves.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    perft_stats(&mut pos, m, 0, &mut stats);
+                }
+                for (total, stats) in total.lock().unwrap().iter_mut().zip(stats) {
+                    *total += stats;
+                }
+            });
+        }
+    });
+    let duration = now.elapsed();
+    println!(
+        "{:>5} {:>14} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+        "depth",
+        "nodes",
+        "captures",
+        "promotions",
+        "drops",
+        "checks",
+        "discovered",
+        "double",
+        "evasions",
+        "checkmates"
+    );
+    for (i, stats) in total.into_inner().unwrap().iter().enumerate() {
+        println!(
+            "{:>5} {:>14} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+            i + 1,
+            stats.nodes,
+            stats.captures,
+            stats.promotions,
+            stats.drops,
+            stats.checks,
+            stats.discovered_checks,
+            stats.double_checks,
+            stats.evasions,
+            stats.checkmates
+        );
+    }
+    println!();
+    println!("Time duration: {:?}", duration);
+}
+
+/// Does the move at `ply` and counts it in `stats[ply]`, then counts the moves after it to the depth of `stats`.
+fn perft_stats(pos: &mut Position, m: Move, ply: usize, stats: &mut [Stats]) {
+    let is_leaf = ply + 1 == stats.len();
+    let s = &mut stats[ply];
+    s.nodes += 1;
+    if pos.in_check() {
+        s.evasions += 1;
+    }
+    match m {
+        Move::Normal { promote: true, .. } => s.promotions += 1,
+        Move::Drop { .. } => s.drops += 1,
+        _ => {}
+    }
+    pos.do_move(m);
+    if pos.captured().is_some() {
+        s.captures += 1;
+    }
+    if pos.in_check() {
+        s.checks += 1;
+        let checkers = pos.checkers();
+        if checkers.count() > 1 {
+            s.double_checks += 1;
+        }
+        if checkers.count() > u8::from(checkers.contains(m.to())) {
+            s.discovered_checks += 1;
+        }
+    }
+    // Moves at the leaves are generated only to find checkmates.
+    if !is_leaf || pos.in_check() {
+        let moves = pos.legal_moves();
+        if moves.is_empty() && pos.in_check() {
+            s.checkmates += 1;
+        }
+        if !is_leaf {
+            for m in moves {
+                perft_stats(pos, m, ply + 1, stats);
+            }
+        }
+    }
+    pos.undo_move(m);
+}
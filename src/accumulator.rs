@@ -0,0 +1,179 @@
+use crate::{PieceValues, Position};
+use shogi_core::{Color, Hand, Piece, PieceKind, Square};
+
+/// Receives the changes of pieces on the board and in hands, to update an evaluation incrementally.
+///
+/// Used with [`Position::do_move_with`] and [`Position::undo_move_with`].
+/// All methods do nothing by default, and `()` is the accumulator that ignores every change.
+pub trait Accumulator {
+    /// The piece is put on the square.
+    #[inline(always)]
+    fn on_add(&mut self, _piece: Piece, _sq: Square) {}
+    /// The piece is removed from the square.
+    #[inline(always)]
+    fn on_remove(&mut self, _piece: Piece, _sq: Square) {}
+    /// The number of the pieces in the player's hand is changed from `before` to `after`.
+    #[inline(always)]
+    fn on_hand_change(&mut self, _c: Color, _pk: PieceKind, _before: u8, _after: u8) {}
+}
+
+impl Accumulator for () {}
+
+/// Material and piece-square table evaluation, from the viewpoint of Black.
+///
+/// Piece-square tables are for Black's pieces, and mirrored for White's.
+/// Pieces in hand are valued the same as the unpromoted pieces on the board.
+#[derive(Debug, Clone)]
+pub struct MaterialPst {
+    values: PieceValues,
+    pst: [[i32; Square::NUM]; PieceKind::NUM],
+    score: i32,
+}
+
+impl MaterialPst {
+    pub fn new(values: PieceValues, pst: [[i32; Square::NUM]; PieceKind::NUM]) -> Self {
+        Self {
+            values,
+            pst,
+            score: 0,
+        }
+    }
+    /// Creates the accumulator with the score of the current position.
+    pub fn with_position(mut self, pos: &Position) -> Self {
+        self.score = 0;
+        pos.accumulate(&mut self);
+        self
+    }
+    /// Score from the viewpoint of Black.
+    #[inline(always)]
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+    /// Score from the viewpoint of the player.
+    #[inline(always)]
+    pub fn score_for(&self, c: Color) -> i32 {
+        match c {
+            Color::Black => self.score,
+            Color::White => -self.score,
+        }
+    }
+    fn piece_score(&self, piece: Piece, sq: Square) -> i32 {
+        let (pk, c) = piece.to_parts();
+        match c {
+            Color::Black => self.values.value(pk) + self.pst[pk.array_index()][sq.array_index()],
+            Color::White => {
+                -self.values.value(pk) - self.pst[pk.array_index()][sq.flip().array_index()]
+            }
+        }
+    }
+}
+
+impl Default for MaterialPst {
+    /// Material only, with the default piece values.
+    fn default() -> Self {
+        Self::new(PieceValues::default(), [[0; Square::NUM]; PieceKind::NUM])
+    }
+}
+
+impl Accumulator for MaterialPst {
+    #[inline(always)]
+    fn on_add(&mut self, piece: Piece, sq: Square) {
+        self.score += self.piece_score(piece, sq);
+    }
+    #[inline(always)]
+    fn on_remove(&mut self, piece: Piece, sq: Square) {
+        self.score -= self.piece_score(piece, sq);
+    }
+    #[inline(always)]
+    fn on_hand_change(&mut self, c: Color, pk: PieceKind, before: u8, after: u8) {
+        let diff = self.values.value(pk) * (i32::from(after) - i32::from(before));
+        match c {
+            Color::Black => self.score += diff,
+            Color::White => self.score -= diff,
+        }
+    }
+}
+
+impl Position {
+    /// Feeds all the pieces of the current position to the accumulator, as added to an empty board and empty hands.
+    pub fn accumulate<A: Accumulator>(&self, acc: &mut A) {
+        for sq in Square::all() {
+            if let Some(p) = self.piece_at(sq) {
+                acc.on_add(p, sq);
+            }
+        }
+        for c in Color::all() {
+            let hand = self.hand(c);
+            for pk in Hand::all_hand_pieces() {
+                let num = hand.count(pk).unwrap_or_default();
+                if num > 0 {
+                    acc.on_hand_change(c, pk, 0, num);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::PartialPosition;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn material() {
+        let pos = Position::default();
+        assert_eq!(0, MaterialPst::default().with_position(&pos).score());
+
+        // P1-KY-KE-GI-KI-OU-KI-GI-KE-KY
+        // P2 * -HI *  *  *  *  * -KA *
+        // P3-FU-FU-FU-FU-FU-FU-FU-FU-FU
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7+FU+FU+FU+FU+FU+FU+FU+FU+FU
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9+KY+KE+GI+KI+OU+KI+GI+KE+KY
+        // P+00HI00KA
+        // -
+        let pos = Position::new(
+            PartialPosition::from_usi(
+                "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/9/LNSGKGSNL w RB 1",
+            )
+            .expect("failed to parse"),
+        );
+        let acc = MaterialPst::default().with_position(&pos);
+        assert_eq!(0, acc.score());
+        assert_eq!(0, acc.score_for(Color::White));
+    }
+
+    #[test]
+    fn do_undo_move() {
+        let mut pst = [[0; Square::NUM]; PieceKind::NUM];
+        for (i, table) in pst.iter_mut().enumerate() {
+            for (j, v) in table.iter_mut().enumerate() {
+                *v = (i * 7 + j * 13) as i32 % 31;
+            }
+        }
+        let mut pos = Position::default();
+        let mut acc = MaterialPst::new(PieceValues::default(), pst).with_position(&pos);
+        let initial = acc.score();
+        let mut moves = Vec::new();
+        // Deterministic playout
+        for i in 0..200 {
+            let legal_moves = pos.legal_moves();
+            if legal_moves.is_empty() {
+                break;
+            }
+            let m = legal_moves[(i * 37) % legal_moves.len()];
+            pos.do_move_with(m, &mut acc);
+            moves.push(m);
+            let expected = MaterialPst::new(PieceValues::default(), pst).with_position(&pos);
+            assert_eq!(expected.score(), acc.score(), "{}", pos.to_sfen());
+        }
+        while let Some(m) = moves.pop() {
+            pos.undo_move_with(m, &mut acc);
+        }
+        assert_eq!(initial, acc.score());
+    }
+}
@@ -0,0 +1,115 @@
+//! Attacks of the pieces, looked up from the precomputed tables.
+
+use crate::tables::{ATTACK_TABLE, BETWEEN_TABLE, LINE_TABLE};
+use crate::Bitboard;
+use shogi_core::{Piece, Square};
+
+/// Squares attacked by the piece on the square, blocked by the occupied squares.
+///
+/// The attacks of sliding pieces include the first occupied square in each direction, whoever occupies it.
+#[inline(always)]
+pub fn attacks_from(piece: Piece, sq: Square, occ: &Bitboard) -> Bitboard {
+    ATTACK_TABLE.attack(piece.piece_kind(), sq, piece.color(), occ)
+}
+
+/// Squares attacked by the piece on the square, as if there were nothing else on the board.
+#[inline(always)]
+pub fn pseudo_attacks(piece: Piece, sq: Square) -> Bitboard {
+    ATTACK_TABLE.attack(piece.piece_kind(), sq, piece.color(), &Bitboard::empty())
+}
+
+/// Squares between the two squares, excluding both of them.
+/// Empty unless they are on the same file, rank or diagonal.
+#[inline(always)]
+pub fn between(sq0: Square, sq1: Square) -> Bitboard {
+    BETWEEN_TABLE[sq0.array_index()][sq1.array_index()]
+}
+
+/// Squares on the whole line from edge to edge through the two squares, including both of them.
+/// Empty unless they are on the same file, rank or diagonal.
+#[inline(always)]
+pub fn line(sq0: Square, sq1: Square) -> Bitboard {
+    LINE_TABLE[sq0.array_index()][sq1.array_index()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::consts::square::*;
+
+    fn to_bb(squares: &[Square]) -> Bitboard {
+        squares
+            .iter()
+            .fold(Bitboard::empty(), |acc, &sq| acc | Bitboard::single(sq))
+    }
+
+    #[test]
+    fn attacks_from() {
+        // 馬 on 5E, blocked at 3C and 7G
+        let occ = to_bb(&[SQ_3C, SQ_7G, SQ_5A]);
+        assert_eq!(
+            to_bb(&[
+                SQ_4D, SQ_3C, SQ_6D, SQ_7C, SQ_8B, SQ_9A, SQ_4F, SQ_3G, SQ_2H, SQ_1I, SQ_6F, SQ_7G,
+                SQ_5D, SQ_5F, SQ_4E, SQ_6E,
+            ]),
+            super::attacks_from(Piece::B_PB, SQ_5E, &occ)
+        );
+        assert_eq!(
+            to_bb(&[SQ_5D, SQ_5C, SQ_5B, SQ_5A]),
+            super::attacks_from(Piece::B_L, SQ_5E, &occ)
+        );
+        assert_eq!(
+            to_bb(&[SQ_5F, SQ_5G, SQ_5H, SQ_5I]),
+            super::attacks_from(Piece::W_L, SQ_5E, &occ)
+        );
+        assert_eq!(
+            to_bb(&[SQ_4C, SQ_6C]),
+            super::attacks_from(Piece::B_N, SQ_5E, &occ)
+        );
+        assert_eq!(
+            to_bb(&[SQ_5D, SQ_4D, SQ_6D, SQ_4E, SQ_6E, SQ_5F]),
+            super::attacks_from(Piece::B_PS, SQ_5E, &occ)
+        );
+    }
+
+    #[test]
+    fn pseudo_attacks() {
+        assert_eq!(16, super::pseudo_attacks(Piece::B_B, SQ_5E).count());
+        assert_eq!(20, super::pseudo_attacks(Piece::W_PB, SQ_5E).count());
+        assert_eq!(16, super::pseudo_attacks(Piece::B_R, SQ_5E).count());
+        assert_eq!(4, super::pseudo_attacks(Piece::W_L, SQ_5E).count());
+        assert_eq!(8, super::pseudo_attacks(Piece::B_K, SQ_5E).count());
+        assert!(super::pseudo_attacks(Piece::B_P, SQ_5A).is_empty());
+    }
+
+    #[test]
+    fn between() {
+        assert_eq!(to_bb(&[SQ_5B, SQ_5C, SQ_5D]), super::between(SQ_5A, SQ_5E));
+        assert_eq!(to_bb(&[SQ_2B, SQ_3C]), super::between(SQ_4D, SQ_1A));
+        assert!(super::between(SQ_5A, SQ_5B).is_empty());
+        assert!(super::between(SQ_5A, SQ_4C).is_empty());
+    }
+
+    #[test]
+    fn line() {
+        let file = to_bb(&[
+            SQ_5A, SQ_5B, SQ_5C, SQ_5D, SQ_5E, SQ_5F, SQ_5G, SQ_5H, SQ_5I,
+        ]);
+        assert_eq!(file, super::line(SQ_5B, SQ_5D));
+        assert_eq!(file, super::line(SQ_5I, SQ_5A));
+        let diagonal = to_bb(&[
+            SQ_1A, SQ_2B, SQ_3C, SQ_4D, SQ_5E, SQ_6F, SQ_7G, SQ_8H, SQ_9I,
+        ]);
+        assert_eq!(diagonal, super::line(SQ_4D, SQ_3C));
+        assert_eq!(to_bb(&[SQ_8A, SQ_9B]), super::line(SQ_9B, SQ_8A));
+        assert!(super::line(SQ_5A, SQ_4C).is_empty());
+        assert!(super::line(SQ_5E, SQ_5E).is_empty());
+        for sq0 in Square::all() {
+            for sq1 in Square::all() {
+                let between = super::between(sq0, sq1);
+                assert_eq!(between, super::line(sq0, sq1) & between);
+                assert_eq!(super::line(sq0, sq1), super::line(sq1, sq0));
+            }
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use std::io;
+use yasai::search::Searcher;
+use yasai::usi::Usi;
+use yasai::MaterialPst;
+
+fn main() -> io::Result<()> {
+    let mut usi = Usi::new(Searcher::new(MaterialPst::default(), 16));
+    usi.run(io::stdin().lock(), io::stdout())?;
+    Ok(())
+}
@@ -1,3 +1,6 @@
+use crate::tables::{FILES, PROMOTION_ZONES, RANKS};
+use shogi_core::Color;
+
 pub(crate) trait Occupied
 where
     Self: Sized,
@@ -89,24 +92,41 @@ cfg_if::cfg_if! {
         target_feature = "avx2"
     ))] {
         mod x86_64;
-        pub(crate) use self::x86_64::Bitboard;
+        pub use self::x86_64::{Bitboard, SquareIterator};
     } else if #[cfg(all(
         feature = "simd",
         target_arch = "aarch64",
         target_feature = "neon"
     ))] {
         mod aarch64;
-        pub(crate) use self::aarch64::Bitboard;
+        pub use self::aarch64::{Bitboard, SquareIterator};
     } else if #[cfg(all(
         feature = "simd",
         target_arch = "wasm32",
         target_feature = "simd128"
     ))] {
         mod wasm32;
-        pub(crate) use self::wasm32::Bitboard;
+        pub use self::wasm32::{Bitboard, SquareIterator};
     } else {
         mod core;
-        pub(crate) use self::core::Bitboard;
+        pub use self::core::{Bitboard, SquareIterator};
+    }
+}
+
+impl Bitboard {
+    /// Squares on the file, from 1 to 9.
+    pub fn file(file: u8) -> Self {
+        assert!((1..=9).contains(&file), "invalid file: {file}");
+        FILES[usize::from(file)]
+    }
+    /// Squares on the rank, from 1 to 9.
+    pub fn rank(rank: u8) -> Self {
+        assert!((1..=9).contains(&rank), "invalid rank: {rank}");
+        RANKS[usize::from(rank)]
+    }
+    /// Promotion zone (敵陣) of the player.
+    pub fn promotion_zone(c: Color) -> Self {
+        PROMOTION_ZONES[c.array_index()]
     }
 }
 
@@ -222,9 +242,78 @@ mod tests {
         assert_eq!(!Bitboard::empty(), odd_files | even_files);
     }
 
+    #[test]
+    fn iterate() {
+        let squares = vec![SQ_1A, SQ_7C, SQ_8G, SQ_9I];
+        assert_eq!(
+            squares,
+            to_bb(squares.clone()).into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Square::all().collect::<Vec<_>>(),
+            (!Bitboard::empty()).into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(None, Bitboard::empty().into_iter().next());
+    }
+
+    #[test]
+    fn convert() {
+        for sq in Square::all() {
+            let bb = Bitboard::single(sq);
+            let core = shogi_core::Bitboard::from(bb);
+            assert_eq!(shogi_core::Bitboard::single(sq), core);
+            assert_eq!(bb, Bitboard::from(core));
+        }
+        let bb = to_bb(vec![SQ_1A, SQ_7C, SQ_8G, SQ_9I]);
+        let core = shogi_core::Bitboard::from(bb);
+        assert_eq!(4, core.count());
+        assert_eq!(bb, Bitboard::from(core));
+        assert_eq!(
+            !shogi_core::Bitboard::empty(),
+            shogi_core::Bitboard::from(!Bitboard::empty())
+        );
+    }
+
+    #[test]
+    fn masks() {
+        assert_eq!(
+            to_bb(vec![
+                SQ_1A, SQ_1B, SQ_1C, SQ_1D, SQ_1E, SQ_1F, SQ_1G, SQ_1H, SQ_1I
+            ]),
+            Bitboard::file(1)
+        );
+        assert_eq!(
+            to_bb(vec![
+                SQ_1E, SQ_2E, SQ_3E, SQ_4E, SQ_5E, SQ_6E, SQ_7E, SQ_8E, SQ_9E
+            ]),
+            Bitboard::rank(5)
+        );
+        assert_eq!(
+            Bitboard::rank(1) | Bitboard::rank(2) | Bitboard::rank(3),
+            Bitboard::promotion_zone(Color::Black)
+        );
+        assert_eq!(
+            Bitboard::rank(7) | Bitboard::rank(8) | Bitboard::rank(9),
+            Bitboard::promotion_zone(Color::White)
+        );
+        let all = (1..=9).fold(Bitboard::empty(), |acc, i| acc | Bitboard::file(i));
+        assert_eq!(!Bitboard::empty(), all);
+        for i in 1..=9 {
+            assert_eq!(9, Bitboard::file(i).count());
+            assert_eq!(9, Bitboard::rank(i).count());
+            assert_eq!(1, (Bitboard::file(i) & Bitboard::rank(i)).count());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_file() {
+        Bitboard::file(0);
+    }
+
     fn to_bb(squares: Vec<Square>) -> Bitboard {
         squares
             .iter()
-            .fold(Bitboard::empty(), |acc, e| (acc | Bitboard::single(*e)))
+            .fold(Bitboard::empty(), |acc, e| acc | Bitboard::single(*e))
     }
 }
@@ -29,8 +29,9 @@ const MASKED_VALUES: [[u64; 2]; Square::NUM + 2] = {
     values
 };
 
+/// Set of squares, in a 128-bit NEON register.
 #[derive(Clone, Copy, Debug)]
-pub(crate) struct Bitboard(aarch64::uint64x2_t);
+pub struct Bitboard(aarch64::uint64x2_t);
 
 impl Bitboard {
     #[inline(always)]
@@ -196,7 +197,26 @@ impl PartialEq for Bitboard {
     }
 }
 
-pub(crate) struct SquareIterator([u64; 2]);
+impl Eq for Bitboard {}
+
+impl From<shogi_core::Bitboard> for Bitboard {
+    #[inline(always)]
+    fn from(bb: shogi_core::Bitboard) -> Self {
+        let u = bb.to_u128();
+        let e = [u as u64, (u >> 64) as u64];
+        Self(unsafe { aarch64::vld1q_u64(e.as_ptr()) })
+    }
+}
+
+impl From<Bitboard> for shogi_core::Bitboard {
+    #[inline(always)]
+    fn from(bb: Bitboard) -> Self {
+        let m = bb.values();
+        unsafe { Self::from_u128_unchecked(m[0] as u128 | (m[1] as u128) << 64) }
+    }
+}
+
+pub struct SquareIterator([u64; 2]);
 
 impl SquareIterator {
     #[inline(always)]
@@ -1,19 +1,21 @@
 use super::Occupied;
-pub(crate) use shogi_core::Bitboard;
 use shogi_core::Square;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+type CoreBitboard = shogi_core::Bitboard;
 
 /// Note the alignment of the bitboard: 18 bits and 63 bits out of 2 64-bit int are used
 const VACANT_MASK_VALUE: u128 = 0x0002_0100_4020_1008_0402_0100;
-const VACANT_MASK: Bitboard = unsafe { Bitboard::from_u128_unchecked(VACANT_MASK_VALUE) };
-const BB_1A: Bitboard = Bitboard::single(Square::SQ_1A);
-const BB_9I: Bitboard = Bitboard::single(Square::SQ_9I);
+const VACANT_MASK: CoreBitboard = unsafe { CoreBitboard::from_u128_unchecked(VACANT_MASK_VALUE) };
+const BB_1A: CoreBitboard = CoreBitboard::single(Square::SQ_1A);
+const BB_9I: CoreBitboard = CoreBitboard::single(Square::SQ_9I);
 
-const MASKED_BBS: [Bitboard; Square::NUM + 2] = {
+const MASKED_BBS: [CoreBitboard; Square::NUM + 2] = {
     let mask = 0x0003_ffff_7fff_ffff_ffff_ffff;
-    let mut bbs = [Bitboard::empty(); Square::NUM + 2];
+    let mut bbs = [CoreBitboard::empty(); Square::NUM + 2];
     let mut i = 0;
     while i < Square::NUM + 2 {
-        bbs[i] = unsafe { Bitboard::from_u128_unchecked(mask & ((1 << i) - 1)) };
+        bbs[i] = unsafe { CoreBitboard::from_u128_unchecked(mask & ((1 << i) - 1)) };
         i += 1;
     }
     bbs
@@ -24,7 +26,7 @@ const MASKED_BBS: [Bitboard; Square::NUM + 2] = {
 /// * `bb` - The occupied bitboard
 /// * `mask` - The potential attacks
 #[inline(always)]
-fn sliding_positive(bb: &Bitboard, mask: &Bitboard) -> Bitboard {
+fn sliding_positive(bb: &CoreBitboard, mask: &CoreBitboard) -> CoreBitboard {
     let tz = (*bb & mask | BB_9I).to_u128().trailing_zeros();
     *mask & MASKED_BBS[tz as usize + 1]
 }
@@ -34,35 +36,62 @@ fn sliding_positive(bb: &Bitboard, mask: &Bitboard) -> Bitboard {
 /// * `bb` - The occupied bitboard
 /// * `mask` - The potential attacks
 #[inline(always)]
-fn sliding_negative(bb: &Bitboard, mask: &Bitboard) -> Bitboard {
+fn sliding_negative(bb: &CoreBitboard, mask: &CoreBitboard) -> CoreBitboard {
     let lz = (*bb & mask | BB_1A).to_u128().leading_zeros();
     *mask & !MASKED_BBS[127 - lz as usize]
 }
 
+/// Set of squares, wrapping `shogi_core::Bitboard`.
+#[derive(Clone, Copy, Debug)]
+pub struct Bitboard(CoreBitboard);
+
+impl Bitboard {
+    #[inline(always)]
+    pub fn empty() -> Self {
+        Self(CoreBitboard::empty())
+    }
+    #[inline(always)]
+    pub fn single(square: Square) -> Self {
+        Self(CoreBitboard::single(square))
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    #[inline(always)]
+    pub fn contains(&self, square: Square) -> bool {
+        self.0.contains(square)
+    }
+    #[inline(always)]
+    pub fn count(self) -> u8 {
+        self.0.count()
+    }
+}
+
 impl Occupied for Bitboard {
     #[inline(always)]
     fn shl(&self) -> Self {
-        unsafe { self.shift_down(1) }
+        Self(unsafe { self.0.shift_down(1) })
     }
     #[inline(always)]
     fn shr(&self) -> Self {
-        unsafe { self.shift_up(1) }
+        Self(unsafe { self.0.shift_up(1) })
     }
     #[inline(always)]
     fn sliding_positive_consecutive(&self, mask: &Self) -> Self {
-        sliding_positive(self, mask)
+        Self(sliding_positive(&self.0, &mask.0))
     }
     #[inline(always)]
     fn sliding_negative_consecutive(&self, mask: &Self) -> Self {
-        sliding_negative(self, mask)
+        Self(sliding_negative(&self.0, &mask.0))
     }
     #[inline(always)]
     fn sliding_positives(&self, masks: &[Self; 2]) -> Self {
-        sliding_positive(self, &masks[0]) | sliding_positive(self, &masks[1])
+        Self(sliding_positive(&self.0, &masks[0].0) | sliding_positive(&self.0, &masks[1].0))
     }
     #[inline(always)]
     fn sliding_negatives(&self, masks: &[Self; 2]) -> Self {
-        sliding_negative(self, &masks[0]) | sliding_negative(self, &masks[1])
+        Self(sliding_negative(&self.0, &masks[0].0) | sliding_negative(&self.0, &masks[1].0))
     }
     #[inline(always)]
     fn vacant_files(&self) -> Self {
@@ -71,8 +100,91 @@ impl Occupied for Bitboard {
         // 2. Shift it by 8 bit to get the flag. Results in either 0b000000000 or 0b000000001
         // 3. 0b100000000 - the value from 2. Results in either 0b100000000 or 0b011111111
         // 4. XOR with 0b100000000. Results in either 0b000000000 or 0b111111111
-        let bb = unsafe { Self::from_u128_unchecked(VACANT_MASK_VALUE - self.to_u128()) };
-        VACANT_MASK
-            ^ unsafe { Self::from_u128_unchecked(VACANT_MASK_VALUE - bb.shift_up(8).to_u128()) }
+        let bb = unsafe { CoreBitboard::from_u128_unchecked(VACANT_MASK_VALUE - self.0.to_u128()) };
+        Self(
+            VACANT_MASK
+                ^ unsafe {
+                    CoreBitboard::from_u128_unchecked(VACANT_MASK_VALUE - bb.shift_up(8).to_u128())
+                },
+        )
+    }
+}
+
+define_bit_trait!(
+    target_trait => BitAnd, assign_trait => BitAndAssign,
+    target_func => bitand, assign_func => bitand_assign,
+    intrinsic => CoreBitboard::and
+);
+
+define_bit_trait!(
+    target_trait => BitOr, assign_trait => BitOrAssign,
+    target_func => bitor, assign_func => bitor_assign,
+    intrinsic => CoreBitboard::or
+);
+
+define_bit_trait!(
+    target_trait => BitXor, assign_trait => BitXorAssign,
+    target_func => bitxor, assign_func => bitxor_assign,
+    intrinsic => CoreBitboard::xor
+);
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    #[inline(always)]
+    fn not(self) -> Self::Output {
+        Bitboard(!self.0)
+    }
+}
+
+impl Not for &Bitboard {
+    type Output = Bitboard;
+
+    #[inline(always)]
+    fn not(self) -> Self::Output {
+        Bitboard(!self.0)
+    }
+}
+
+impl PartialEq for Bitboard {
+    #[inline(always)]
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Bitboard {}
+
+impl From<CoreBitboard> for Bitboard {
+    #[inline(always)]
+    fn from(bb: CoreBitboard) -> Self {
+        Self(bb)
+    }
+}
+
+impl From<Bitboard> for CoreBitboard {
+    #[inline(always)]
+    fn from(bb: Bitboard) -> Self {
+        bb.0
+    }
+}
+
+pub struct SquareIterator(CoreBitboard);
+
+impl Iterator for SquareIterator {
+    type Item = Square;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop()
+    }
+}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = SquareIterator;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SquareIterator(self.0)
     }
 }
@@ -31,8 +31,9 @@ const MASKED_VALUES: [wasm32::v128; Square::NUM + 2] = {
 const ZERO: wasm32::v128 = wasm32::u64x2(0, 0);
 const ONES: wasm32::v128 = wasm32::u64x2(0x7fff_ffff_ffff_ffff, 0x0003_ffff);
 
+/// Set of squares, in a 128-bit WebAssembly SIMD vector.
 #[derive(Clone, Copy, Debug)]
-pub(crate) struct Bitboard(wasm32::v128);
+pub struct Bitboard(wasm32::v128);
 
 impl Bitboard {
     #[inline(always)]
@@ -167,7 +168,25 @@ impl PartialEq for Bitboard {
     }
 }
 
-pub(crate) struct SquareIterator([u64; 2]);
+impl Eq for Bitboard {}
+
+impl From<shogi_core::Bitboard> for Bitboard {
+    #[inline(always)]
+    fn from(bb: shogi_core::Bitboard) -> Self {
+        let u = bb.to_u128();
+        Self(wasm32::u64x2(u as u64, (u >> 64) as u64))
+    }
+}
+
+impl From<Bitboard> for shogi_core::Bitboard {
+    #[inline(always)]
+    fn from(bb: Bitboard) -> Self {
+        let m = bb.values();
+        unsafe { Self::from_u128_unchecked(m[0] as u128 | (m[1] as u128) << 64) }
+    }
+}
+
+pub struct SquareIterator([u64; 2]);
 
 impl SquareIterator {
     #[inline(always)]
@@ -37,8 +37,9 @@ const MASKED_VALUES: [(i64, i64); 16] = [
     (-1, 0x00ff_ffff_ffff_ffff),
 ];
 
+/// Set of squares, in a 128-bit SSE register.
 #[derive(Clone, Copy, Debug)]
-pub(crate) struct Bitboard(x86_64::__m128i);
+pub struct Bitboard(x86_64::__m128i);
 
 impl Bitboard {
     #[inline(always)]
@@ -221,7 +222,25 @@ impl PartialEq for Bitboard {
     }
 }
 
-pub(crate) struct SquareIterator([i64; 2]);
+impl Eq for Bitboard {}
+
+impl From<shogi_core::Bitboard> for Bitboard {
+    #[inline(always)]
+    fn from(bb: shogi_core::Bitboard) -> Self {
+        let u = bb.to_u128();
+        Self(unsafe { x86_64::_mm_set_epi64x((u >> 64) as i64, u as i64) })
+    }
+}
+
+impl From<Bitboard> for shogi_core::Bitboard {
+    #[inline(always)]
+    fn from(bb: Bitboard) -> Self {
+        let m = bb.values();
+        unsafe { Self::from_u128_unchecked(m[0] as u64 as u128 | (m[1] as u64 as u128) << 64) }
+    }
+}
+
+pub struct SquareIterator([i64; 2]);
 
 impl SquareIterator {
     #[inline(always)]
@@ -0,0 +1,165 @@
+use crate::tables::PROMOTION_ZONES;
+use crate::Position;
+use shogi_core::{Color, Hand, Move, PieceKind, Square};
+
+/// Rules for winning by declaration of entering king (入玉宣言).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnteringKingRule {
+    /// 24点法: 31 points or more are required for both players.
+    Point24,
+    /// 27点法 (CSA rule): 28 points or more for Black, 27 points or more for White.
+    Point27,
+    /// トライルール: the king reaches the initial square of the opponent's king.
+    Try,
+}
+
+impl Position {
+    /// Checks if the side to move can declare win by entering king with the rule.
+    ///
+    /// For the point rules, all of the following conditions are required:
+    /// - the king is in the promotion zone
+    /// - the king is not in check
+    /// - 10 or more pieces other than the king are in the promotion zone
+    /// - the points of pieces in the promotion zone and in hand are enough,
+    ///   where rooks and bishops are counted as 5 points and the others as 1 point
+    ///
+    /// For the try rule, the king must be able to move to the initial square of the opponent's king.
+    pub fn can_declare_win(&self, rule: EnteringKingRule) -> bool {
+        let c = self.side_to_move();
+        let king = match self.king_position(c) {
+            Some(sq) => sq,
+            None => return false,
+        };
+        let required_points = match (rule, c) {
+            (EnteringKingRule::Try, _) => {
+                let m = Move::Normal {
+                    from: king,
+                    to: [Square::SQ_5A, Square::SQ_5I][c.array_index()],
+                    promote: false,
+                };
+                return self.is_pseudo_legal(m) && self.is_legal(m);
+            }
+            (EnteringKingRule::Point24, _) => 31,
+            (EnteringKingRule::Point27, Color::Black) => 28,
+            (EnteringKingRule::Point27, Color::White) => 27,
+        };
+        let zone = PROMOTION_ZONES[c.array_index()];
+        if !zone.contains(king) || self.in_check() {
+            return false;
+        }
+        let pieces = self.player_bitboard(c) & zone & !self.piece_kind_bitboard(PieceKind::King);
+        let bigs = pieces
+            & (self.piece_kind_bitboard(PieceKind::Bishop)
+                | self.piece_kind_bitboard(PieceKind::Rook)
+                | self.piece_kind_bitboard(PieceKind::ProBishop)
+                | self.piece_kind_bitboard(PieceKind::ProRook));
+        if pieces.count() < 10 {
+            return false;
+        }
+        let hand = self.hand(c);
+        let hand_points = Hand::all_hand_pieces()
+            .map(|pk| {
+                let num = hand.count(pk).unwrap_or_default();
+                match pk {
+                    PieceKind::Bishop | PieceKind::Rook => num * 5,
+                    _ => num,
+                }
+            })
+            .sum::<u8>();
+        pieces.count() + bigs.count() * 4 + hand_points >= required_points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::PartialPosition;
+    use shogi_usi_parser::FromUsi;
+
+    #[allow(clippy::bool_assert_comparison)]
+    #[test]
+    fn can_declare_win() {
+        let test_cases = [
+            // 28 points in the promotion zone
+            // P1+HI+HI *  *  *  * +KA+KA *
+            // P2+KI+KI+KI+KI+OU+GI+GI+GI+GI
+            // P3 *  *  *  *  *  *  *  *  *
+            // P4 *  *  *  *  *  *  *  *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  * -OU *  *  *  *
+            // P-00AL
+            // +
+            (
+                "sfen RR4BB1/GGGGKSSSS/9/9/9/9/9/9/4k4 b 4n4l18p 1",
+                [false, true, true],
+            ),
+            // 27 points: 1 point short for Black
+            (
+                "sfen RR4BB1/GGGGKSSS1/9/9/9/9/9/9/4k4 b s4n4l18p 1",
+                [false, false, true],
+            ),
+            // 9 pieces in the promotion zone, even with enough points in hand
+            (
+                "sfen RR4BB1/GG2KSSS1/9/9/9/9/9/9/4k4 b 2GS4N4L18P 1",
+                [false, false, true],
+            ),
+            // 31 points with the pieces in hand
+            (
+                "sfen RR4BB1/GGGGKSSSS/9/9/9/9/9/9/4k4 b 3Pr4n4l15p 1",
+                [true, true, true],
+            ),
+            // In check
+            (
+                "sfen RR4BB1/GGG1KSSSS/4g4/9/9/9/9/9/4k4 b P4n4l17p 1",
+                [false, false, true],
+            ),
+            // The king can move to 5A
+            // P1 *  *  *  *  *  *  *  *  *
+            // P2 *  *  *  * +OU *  *  *  *
+            // P3 *  *  *  *  *  *  *  *  *
+            // P4 *  *  *  *  *  *  *  *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  * -OU *  *  *  *
+            // P-00AL
+            // +
+            (
+                "sfen 9/4K4/9/9/9/9/9/9/4k4 b 2r2b4g4s4n4l18p 1",
+                [false, false, true],
+            ),
+            // The king can capture the lance on 5A
+            (
+                "sfen 4l4/4K4/9/9/9/9/9/9/4k4 b 2r2b4g4s4n3l18p 1",
+                [false, false, true],
+            ),
+            // 5A is protected by a silver
+            (
+                "sfen 9/4Ks3/9/9/9/9/9/9/4k4 b 2r2b4g3s4n4l18p 1",
+                [false, false, false],
+            ),
+            // 28 points for White
+            (
+                "sfen 4K4/9/9/9/9/9/9/ssssk1ggg/1bb4rr w g4N4L18P 1",
+                [false, true, true],
+            ),
+        ];
+        for (sfen, expected) in test_cases {
+            let pos = Position::new(PartialPosition::from_usi(sfen).expect("failed to parse"));
+            for (rule, expected) in [
+                EnteringKingRule::Point24,
+                EnteringKingRule::Point27,
+                EnteringKingRule::Try,
+            ]
+            .into_iter()
+            .zip(expected)
+            {
+                assert_eq!(expected, pos.can_declare_win(rule), "{sfen} {rule:?}");
+            }
+        }
+    }
+}
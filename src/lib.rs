@@ -1,7 +1,23 @@
+mod accumulator;
+pub mod attacks;
 mod bitboard;
+mod entering_king;
+mod mate;
 mod movegen;
 mod position;
+pub mod search;
+mod see;
 mod tables;
+pub mod tsume;
+pub mod tt;
+#[cfg(feature = "usi")]
+pub mod usi;
+mod validation;
 mod zobrist;
 
-pub use position::Position;
+pub use accumulator::{Accumulator, MaterialPst};
+pub use bitboard::{Bitboard, SquareIterator};
+pub use entering_king::EnteringKingRule;
+pub use position::{DirtyPiece, MoveError, PieceLocation, Position, RepetitionStatus};
+pub use see::PieceValues;
+pub use validation::PositionError;
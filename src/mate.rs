@@ -0,0 +1,97 @@
+use crate::Position;
+use shogi_core::Move;
+
+impl Position {
+    /// Finds a move to checkmate the opponent in one move (一手詰め), if exists.
+    ///
+    /// Only checking moves are tried, and a move is a checkmate if the opponent has no legal move to evade it.
+    /// Dropping a pawn to checkmate (打ち歩詰め) is never returned since it's not a legal move.
+    pub fn mate_in_one(&self) -> Option<Move> {
+        let moves = self.check_moves();
+        if moves.is_empty() {
+            return None;
+        }
+        // The moves are tried on a copy without the history, which is enough to find evasions.
+        let mut pos = self.without_history();
+        moves.into_iter().find(|&m| {
+            pos.do_move(m);
+            let is_mate = !pos.has_evasion();
+            pos.undo_move(m);
+            is_mate
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{PartialPosition, Piece, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn mate_in_one() {
+        let test_cases = [
+            // 頭金
+            // P1 *  *  *  * -OU *  *  *  *
+            // P2 *  *  *  *  *  *  *  *  *
+            // P3 *  *  *  * +FU *  *  *  *
+            // P4 *  *  *  *  *  *  *  *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  * +OU *  *  *  *
+            // P+00KI
+            // P-00AL
+            // +
+            (
+                "sfen 4k4/9/4P4/9/9/9/9/9/4K4 b G2r2b3g4s4n4l17p 1",
+                Some(Move::Drop {
+                    to: Square::SQ_5B,
+                    piece: Piece::B_G,
+                }),
+            ),
+            // 両王手
+            // P1 *  *  *  *  *  *  * -KE-OU
+            // P2 *  *  *  *  *  *  * -FU *
+            // P3 *  *  *  *  *  *  *  *  *
+            // P4 *  *  *  *  *  *  *  *  *
+            // P5 *  *  *  *  *  *  *  * +KE
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  * +OU *  *  * +KY
+            // +
+            (
+                "sfen 7nk/7p1/9/9/8N/9/9/9/4K3L b - 1",
+                Some(Move::Normal {
+                    from: Square::SQ_1E,
+                    to: Square::SQ_2C,
+                    promote: false,
+                }),
+            ),
+            // 打ち歩詰め
+            // P1 *  *  *  *  *  *  *  *  *
+            // P2 *  *  *  *  *  *  * -FU-FU
+            // P3 *  *  *  *  *  *  *  * -OU
+            // P4 *  *  *  *  *  *  * +FU *
+            // P5 *  *  *  *  *  *  * +KI *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  *  *  *  *  *  *
+            // P+00FU
+            // P-00AL
+            // +
+            ("sfen 9/7pp/8k/7P1/7G1/9/9/9/9 b P2r2b3g4s4n4l14p 1", None),
+            (
+                "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+                None,
+            ),
+        ];
+        for (sfen, expected) in test_cases {
+            let pos = Position::new(PartialPosition::from_usi(sfen).expect("failed to parse"));
+            assert_eq!(expected, pos.mate_in_one(), "{sfen}");
+        }
+    }
+}
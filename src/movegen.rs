@@ -6,56 +6,173 @@ use shogi_core::{Color, Hand, Move, Piece, PieceKind, Square};
 
 const MAX_LEGAL_MOVES: usize = 593;
 
+/// Keep only the moves after `start` which satisfy `f`. The order of moves is not preserved.
+fn retain_moves<F>(av: &mut ArrayVec<Move, MAX_LEGAL_MOVES>, start: usize, f: F)
+where
+    F: Fn(Move) -> bool,
+{
+    let mut i = start;
+    while i != av.len() {
+        if f(av[i]) {
+            i += 1;
+        } else {
+            av.swap_remove(i);
+        }
+    }
+}
+
 impl Position {
     pub fn legal_moves(&self) -> ArrayVec<Move, MAX_LEGAL_MOVES> {
+        let mut av = self.pseudo_legal_moves();
+        retain_moves(&mut av, 0, |m| self.is_legal(m));
+        av
+    }
+    /// Pseudo-legal moves, which may leave the king in check.
+    /// Each move must be verified with [`Position::is_legal`] before it is played.
+    pub fn pseudo_legal_moves(&self) -> ArrayVec<Move, MAX_LEGAL_MOVES> {
         let mut av = ArrayVec::new();
         if self.in_check() {
             self.generate_evasions(&mut av);
         } else {
             self.generate_all(&mut av);
         }
-
-        let mut i = 0;
-        while i != av.len() {
-            if self.is_legal(av[i]) {
-                i += 1;
-            } else {
-                av.swap_remove(i);
-            }
+        av
+    }
+    /// Legal captures and promotions, the first stage of staged move generation.
+    /// When in check, these are the evasions which capture or promote.
+    pub fn capture_moves(&self) -> ArrayVec<Move, MAX_LEGAL_MOVES> {
+        let mut av = ArrayVec::new();
+        if self.in_check() {
+            self.generate_evasions(&mut av);
+            retain_moves(&mut av, 0, |m| self.is_capture_or_promotion(m));
+        } else {
+            self.generate_captures(&mut av);
+        }
+        retain_moves(&mut av, 0, |m| self.is_legal(m));
+        av
+    }
+    /// Legal moves which neither capture nor promote, including drops.
+    /// When in check, these are the remaining evasions.
+    pub fn quiet_moves(&self) -> ArrayVec<Move, MAX_LEGAL_MOVES> {
+        let mut av = ArrayVec::new();
+        if self.in_check() {
+            self.generate_evasions(&mut av);
+            retain_moves(&mut av, 0, |m| !self.is_capture_or_promotion(m));
+        } else {
+            self.generate_quiets(&mut av);
         }
+        retain_moves(&mut av, 0, |m| self.is_legal(m));
         av
     }
+    /// Legal moves which give check, both direct and discovered, including drops.
+    pub fn check_moves(&self) -> ArrayVec<Move, MAX_LEGAL_MOVES> {
+        let mut av = ArrayVec::new();
+        if self.in_check() {
+            self.generate_evasions(&mut av);
+            retain_moves(&mut av, 0, |m| self.is_check_move(m));
+        } else {
+            self.generate_checks(&mut av);
+        }
+        retain_moves(&mut av, 0, |m| self.is_legal(m));
+        av
+    }
+    /// Legal moves to evade check. Empty if not in check.
+    pub fn evasion_moves(&self) -> ArrayVec<Move, MAX_LEGAL_MOVES> {
+        let mut av = ArrayVec::new();
+        if self.in_check() {
+            self.generate_evasions(&mut av);
+            retain_moves(&mut av, 0, |m| self.is_legal(m));
+        }
+        av
+    }
+    /// Checks if any legal evasion exists, stopping at the first one. False if not in check.
+    pub(crate) fn has_evasion(&self) -> bool {
+        let mut av = ArrayVec::new();
+        if self.in_check() {
+            self.generate_evasions(&mut av);
+        }
+        av.into_iter().any(|m| self.is_legal(m))
+    }
+    fn is_capture_or_promotion(&self, m: Move) -> bool {
+        match m {
+            Move::Normal { to, promote, .. } => promote || self.piece_at(to).is_some(),
+            Move::Drop { .. } => false,
+        }
+    }
     /// Generate moves.
     fn generate_all(&self, av: &mut ArrayVec<Move, MAX_LEGAL_MOVES>) {
         let target = !self.player_bitboard(self.side_to_move());
-        self.generate_for_fu(av, &target);
-        self.generate_for_ky(av, &target);
-        self.generate_for_ke(av, &target);
-        self.generate_for_gi(av, &target);
-        self.generate_for_ka(av, &target);
-        self.generate_for_hi(av, &target);
-        self.generate_for_ki(av, &target);
-        self.generate_for_ou(av, &target);
-        self.generate_for_um(av, &target);
-        self.generate_for_ry(av, &target);
+        self.generate_for_board(av, &target);
         self.generate_drop(av, &(!self.occupied_bitboard() & !Bitboard::empty()));
     }
+    /// Generate captures and non-capturing promotions.
+    fn generate_captures(&self, av: &mut ArrayVec<Move, MAX_LEGAL_MOVES>) {
+        let target = self.player_bitboard(self.side_to_move().flip());
+        self.generate_for_board(av, &target);
+        let start = av.len();
+        self.generate_for_board(av, &!self.occupied_bitboard());
+        retain_moves(av, start, |m| {
+            matches!(m, Move::Normal { promote: true, .. })
+        });
+    }
+    /// Generate non-capturing moves without promotion, including drops.
+    fn generate_quiets(&self, av: &mut ArrayVec<Move, MAX_LEGAL_MOVES>) {
+        let target = !self.occupied_bitboard();
+        let start = av.len();
+        self.generate_for_board(av, &target);
+        retain_moves(av, start, |m| {
+            !matches!(m, Move::Normal { promote: true, .. })
+        });
+        self.generate_drop(av, &target);
+    }
+    /// Generate checking moves, optimized using AttackInfo.
+    fn generate_checks(&self, av: &mut ArrayVec<Move, MAX_LEGAL_MOVES>) {
+        let c = self.side_to_move();
+        let occ = self.occupied_bitboard();
+        let target = !self.player_bitboard(c);
+        // 開き王手の候補
+        let discoverers = self.pinned_bitboard(c.flip()) & self.player_bitboard(c);
+        for from in self.player_bitboard(c) {
+            let pk = self.piece_at(from).unwrap().piece_kind();
+            let mut to_bb = ATTACK_TABLE.attack(pk, from, c, &occ) & target;
+            if !discoverers.contains(from) {
+                let mut checkables = self.checkables(pk);
+                if let Some(promoted) = pk.promote() {
+                    checkables |= self.checkables(promoted);
+                }
+                to_bb &= checkables;
+            }
+            for to in to_bb {
+                push_normal_moves(av, pk, from, to, c);
+            }
+        }
+        let hand = self.hand(c);
+        let mut target_drop = Bitboard::empty();
+        for pk in Hand::all_hand_pieces().filter(|&pk| hand.count(pk).unwrap_or_default() > 0) {
+            target_drop |= self.checkables(pk);
+        }
+        self.generate_drop(av, &(target_drop & !occ));
+        // Candidates include non-checking moves with or without promotion, and off the discovered line.
+        retain_moves(av, 0, |m| self.is_check_move(m));
+    }
+    /// Generate moves of all pieces on the board to the target.
+    fn generate_for_board(&self, av: &mut ArrayVec<Move, MAX_LEGAL_MOVES>, target: &Bitboard) {
+        self.generate_for_fu(av, target);
+        self.generate_for_ky(av, target);
+        self.generate_for_ke(av, target);
+        self.generate_for_gi(av, target);
+        self.generate_for_ka(av, target);
+        self.generate_for_hi(av, target);
+        self.generate_for_ki(av, target);
+        self.generate_for_ou(av, target);
+        self.generate_for_um(av, target);
+        self.generate_for_ry(av, target);
+    }
     /// Generate moves to evade check, optimized using AttackInfo.
     fn generate_evasions(&self, av: &mut ArrayVec<Move, MAX_LEGAL_MOVES>) {
         let c = self.side_to_move();
         let king = self.king_position(c).unwrap();
-        let mut checkers_attacks = Bitboard::empty();
-        let mut checkers_count = 0;
-        for ch in self.checkers() {
-            let pk = self.piece_at(ch).unwrap().piece_kind();
-            // 龍が斜め位置から王手している場合のみ、他の駒の裏に逃がれることができる可能性がある
-            if pk == PieceKind::ProRook && ch.file() != king.file() && ch.rank() != king.rank() {
-                checkers_attacks |= ATTACK_TABLE.hi.attack(ch, &self.occupied_bitboard());
-            } else {
-                checkers_attacks |= ATTACK_TABLE.pseudo_attack(pk, ch, c.flip());
-            }
-            checkers_count += 1;
-        }
+        let checkers_attacks = self.checkers_attacks(king);
         for to in ATTACK_TABLE.ou.attack(king, c) & !self.player_bitboard(c) & !checkers_attacks {
             av.push(Move::Normal {
                 from: king,
@@ -64,12 +181,12 @@ impl Position {
             });
         }
         // 両王手の場合は玉が逃げるしかない
-        if checkers_count > 1 {
+        if self.checkers_bitboard().count() > 1 {
             return;
         }
-        let ch = self.checkers().into_iter().next().unwrap();
+        let ch = self.checkers_bitboard().into_iter().next().unwrap();
         let target_drop = BETWEEN_TABLE[ch.array_index()][king.array_index()];
-        let target_move = target_drop | self.checkers();
+        let target_move = target_drop | self.checkers_bitboard();
         self.generate_for_fu(av, &target_move);
         self.generate_for_ky(av, &target_move);
         self.generate_for_ke(av, &target_move);
@@ -84,6 +201,21 @@ impl Position {
             self.generate_drop(av, &target_drop);
         }
     }
+    /// Squares which the king can't escape to, including those hidden behind the king.
+    fn checkers_attacks(&self, king: Square) -> Bitboard {
+        let c = self.side_to_move();
+        let mut checkers_attacks = Bitboard::empty();
+        for ch in self.checkers_bitboard() {
+            let pk = self.piece_at(ch).unwrap().piece_kind();
+            // 龍が斜め位置から王手している場合のみ、他の駒の裏に逃がれることができる可能性がある
+            if pk == PieceKind::ProRook && ch.file() != king.file() && ch.rank() != king.rank() {
+                checkers_attacks |= ATTACK_TABLE.hi.attack(ch, &self.occupied_bitboard());
+            } else {
+                checkers_attacks |= ATTACK_TABLE.pseudo_attack(pk, ch, c.flip());
+            }
+        }
+        checkers_attacks
+    }
     fn generate_for_fu(&self, av: &mut ArrayVec<Move, MAX_LEGAL_MOVES>, target: &Bitboard) {
         let c = self.side_to_move();
         let (to_bb, delta) = [
@@ -308,33 +440,116 @@ impl Position {
             }
             let piece = Piece::new(pk, c);
             for to in target {
-                if match pk {
-                    PieceKind::Pawn | PieceKind::Lance => {
-                        RELATIVE_RANKS[to.array_index()][c.array_index()] > 1
-                    }
-                    PieceKind::Knight => RELATIVE_RANKS[to.array_index()][c.array_index()] > 2,
-                    _ => true,
-                } {
+                if is_movable_rank(pk, to, c) {
                     av.push(Move::Drop { to, piece });
                 }
             }
         }
     }
-    // Checks if the move isn't illegal: king's suicidal moves and moving pinned piece away.
-    fn is_legal(&self, m: Move) -> bool {
+    /// Checks if an arbitrary move, e.g. from a transposition table or user input, is pseudo-legal:
+    /// that is, it would be generated by [`Position::pseudo_legal_moves`].
+    pub fn is_pseudo_legal(&self, m: Move) -> bool {
+        let c = self.side_to_move();
+        let checkers = self.checkers_bitboard();
+        match m {
+            Move::Normal { from, to, promote } => {
+                let piece = match self.piece_at(from) {
+                    Some(p) if p.color() == c => p,
+                    _ => return false,
+                };
+                if self.player_bitboard(c).contains(to)
+                    || !ATTACK_TABLE
+                        .attack(piece.piece_kind(), from, c, &self.occupied_bitboard())
+                        .contains(to)
+                {
+                    return false;
+                }
+                let pk = piece.piece_kind();
+                if promote {
+                    if pk.promote().is_none()
+                        || !(PROMOTABLE[from.array_index()][c.array_index()]
+                            || PROMOTABLE[to.array_index()][c.array_index()])
+                    {
+                        return false;
+                    }
+                } else if !is_movable_rank(pk, to, c) {
+                    return false;
+                }
+                if !checkers.is_empty() {
+                    if pk == PieceKind::King {
+                        return !self.checkers_attacks(from).contains(to);
+                    }
+                    return checkers.count() == 1 && self.is_interposition(to, true);
+                }
+            }
+            Move::Drop { to, piece } => {
+                if piece.color() != c
+                    || self.hand(c).count(piece.piece_kind()).unwrap_or_default() == 0
+                    || self.piece_at(to).is_some()
+                    || !self.is_valid_drop(piece.piece_kind(), to)
+                {
+                    return false;
+                }
+                if !checkers.is_empty() {
+                    return checkers.count() == 1 && self.is_interposition(to, false);
+                }
+            }
+        }
+        true
+    }
+    /// Checks the rules of dropping the piece of the side to move: dead ranks, nifu and uchifuzume.
+    pub(crate) fn is_valid_drop(&self, pk: PieceKind, to: Square) -> bool {
+        let c = self.side_to_move();
+        if !is_movable_rank(pk, to, c) {
+            return false;
+        }
+        if pk == PieceKind::Pawn {
+            // 二歩
+            if !(self.player_bitboard(c) & self.piece_kind_bitboard(PieceKind::Pawn))
+                .vacant_files()
+                .contains(to)
+            {
+                return false;
+            }
+            // 打ち歩詰め
+            if let Some(sq) = self.king_position(c.flip()) {
+                if ATTACK_TABLE.fu.attack(sq, c.flip()).contains(to) && self.is_pawn_drop_mate(to) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+    /// Checks if a move to the square blocks or captures (if `capture` is true) the only checker.
+    fn is_interposition(&self, to: Square, capture: bool) -> bool {
+        let c = self.side_to_move();
+        match (
+            self.king_position(c),
+            self.checkers_bitboard().into_iter().next(),
+        ) {
+            (Some(king), Some(ch)) => {
+                (capture && ch == to)
+                    || BETWEEN_TABLE[ch.array_index()][king.array_index()].contains(to)
+            }
+            _ => false,
+        }
+    }
+    /// Checks if a pseudo-legal move isn't illegal: king's suicidal moves and moving pinned piece away.
+    /// The move is assumed to be generated by [`Position::pseudo_legal_moves`].
+    pub fn is_legal(&self, m: Move) -> bool {
         if let Some(from) = m.from() {
             let c = self.side_to_move();
             let king = [Piece::B_K, Piece::W_K][c.array_index()];
             // 玉が相手の攻撃範囲内に動いてしまう指し手は除外
             if self.piece_at(from) == Some(king)
                 && !self
-                    .attackers_to(c.flip(), m.to(), &self.occupied_bitboard())
+                    .attackers_to_bitboard(c.flip(), m.to(), &self.occupied_bitboard())
                     .is_empty()
             {
                 return false;
             }
             // 飛び駒から守っている駒が直線上から外れてしまう指し手は除外
-            if self.pinned(c).contains(from) {
+            if self.pinned_bitboard(c).contains(from) {
                 if let Some(sq) = self.king_position(c) {
                     if !(BETWEEN_TABLE[sq.array_index()][from.array_index()].contains(m.to())
                         || BETWEEN_TABLE[sq.array_index()][m.to().array_index()].contains(from))
@@ -350,7 +565,7 @@ impl Position {
         let c = self.side_to_move();
         // 玉自身が歩を取れる
         if self
-            .attackers_to(c, sq, &self.occupied_bitboard())
+            .attackers_to_bitboard(c, sq, &self.occupied_bitboard())
             .is_empty()
         {
             return false;
@@ -358,7 +573,7 @@ impl Position {
         // 他の駒が歩を取れる
         // 飛/龍がまっすぐ引いて取るのは常に可能
         let capture_candidates = self.attackers_to_except_klp(c.flip(), sq);
-        if !(capture_candidates & (!self.pinned(c.flip()) | FILES[usize::from(sq.file())]))
+        if !(capture_candidates & (!self.pinned_bitboard(c.flip()) | FILES[usize::from(sq.file())]))
             .is_empty()
         {
             return false;
@@ -370,7 +585,7 @@ impl Position {
                 ATTACK_TABLE.ou.attack(king, c.flip()) & !self.player_bitboard(c.flip()) & !single;
             let occupied = self.occupied_bitboard() | single;
             for to in escape {
-                if self.attackers_to(c, to, &occupied).is_empty() {
+                if self.attackers_to_bitboard(c, to, &occupied).is_empty() {
                     return false;
                 }
             }
@@ -378,7 +593,7 @@ impl Position {
         true
     }
     #[rustfmt::skip]
-    fn attackers_to(&self, c: Color, to: Square, occ: &Bitboard) -> Bitboard {
+    pub(crate) fn attackers_to_bitboard(&self, c: Color, to: Square, occ: &Bitboard) -> Bitboard {
         let opp = c.flip();
         (     (ATTACK_TABLE.fu.attack(to, opp)      & self.piece_kind_bitboard(PieceKind::Pawn))
             | (ATTACK_TABLE.ky.attack(to, opp, occ) & self.piece_kind_bitboard(PieceKind::Lance))
@@ -403,10 +618,46 @@ impl Position {
     }
 }
 
+/// Push a normal move with and/or without promotion, as far as allowed.
+fn push_normal_moves(
+    av: &mut ArrayVec<Move, MAX_LEGAL_MOVES>,
+    pk: PieceKind,
+    from: Square,
+    to: Square,
+    c: Color,
+) {
+    if pk.promote().is_some()
+        && (PROMOTABLE[from.array_index()][c.array_index()]
+            || PROMOTABLE[to.array_index()][c.array_index()])
+    {
+        av.push(Move::Normal {
+            from,
+            to,
+            promote: true,
+        });
+    }
+    if is_movable_rank(pk, to, c) {
+        av.push(Move::Normal {
+            from,
+            to,
+            promote: false,
+        });
+    }
+}
+
+/// Checks if the piece still has any moves at the square, i.e. it isn't on a dead rank.
+pub(crate) fn is_movable_rank(pk: PieceKind, to: Square, c: Color) -> bool {
+    match pk {
+        PieceKind::Pawn | PieceKind::Lance => RELATIVE_RANKS[to.array_index()][c.array_index()] > 1,
+        PieceKind::Knight => RELATIVE_RANKS[to.array_index()][c.array_index()] > 2,
+        _ => true,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use shogi_core::PartialPosition;
+    use shogi_core::{PartialPosition, ToUsi};
     use shogi_usi_parser::FromUsi;
 
     #[test]
@@ -466,6 +717,181 @@ mod tests {
         assert_eq!(593, pos.legal_moves().len());
     }
 
+    #[test]
+    fn staged_moves() {
+        fn sorted(moves: &[Move]) -> Vec<String> {
+            let mut v = moves.iter().map(|m| m.to_usi_owned()).collect::<Vec<_>>();
+            v.sort();
+            v
+        }
+
+        for sfen in [
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+            "sfen lnsgkg1nl/1r5s1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL b Bb 1",
+            "sfen R8/2K1S1SSk/4B4/9/9/9/9/9/1L1L1L3 b RBGSNLP3g3n17p 1",
+            "sfen 9/9/9/9/9/7p1/7+r1/6KN1/4k1S2 b Pr2b4g3s3n4l16p 1",
+            "sfen 6p1k/9/6P1G/9/8L/9/9/9/9 b RBLrb3g4s4n2l16p 1",
+        ] {
+            let pos = Position::new(PartialPosition::from_usi(sfen).expect("failed to parse"));
+            let legal_moves = pos.legal_moves();
+            let captures = pos.capture_moves();
+            let quiets = pos.quiet_moves();
+            assert!(captures.iter().all(|&m| pos.is_capture_or_promotion(m)));
+            assert!(quiets.iter().all(|&m| !pos.is_capture_or_promotion(m)));
+            assert_eq!(
+                sorted(&legal_moves),
+                sorted(&[captures.as_slice(), quiets.as_slice()].concat()),
+                "{sfen}"
+            );
+            let checks = legal_moves
+                .iter()
+                .copied()
+                .filter(|&m| pos.is_check_move(m))
+                .collect::<Vec<_>>();
+            assert_eq!(sorted(&checks), sorted(&pos.check_moves()), "{sfen}");
+            if pos.in_check() {
+                assert_eq!(sorted(&legal_moves), sorted(&pos.evasion_moves()));
+            } else {
+                assert!(pos.evasion_moves().is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn pseudo_legal_moves() {
+        // P1 *  *  *  *  *  *  *  *  *
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  *  *  *  *  *  *
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  * -HI *  *  *  *
+        // P8 *  *  *  * +KI *  *  *  *
+        // P9 *  *  *  * +OU *  *  * -OU
+        // P-00AL
+        // +
+        let pos = Position::new(
+            PartialPosition::from_usi("sfen 9/9/9/9/9/9/4r4/4G4/4K3k b r2b3g4s4n4l18p 1")
+                .expect("failed to parse"),
+        );
+        let pseudo_legal_moves = pos.pseudo_legal_moves();
+        let legal_moves = pos.legal_moves();
+        assert!(pseudo_legal_moves.len() > legal_moves.len());
+        assert_eq!(
+            legal_moves.len(),
+            pseudo_legal_moves
+                .iter()
+                .filter(|&&m| pos.is_legal(m))
+                .count()
+        );
+        // The pinned gold can only capture the rook
+        assert!(!pos.is_legal(Move::Normal {
+            from: Square::SQ_5H,
+            to: Square::SQ_4H,
+            promote: false,
+        }));
+        assert!(pos.is_legal(Move::Normal {
+            from: Square::SQ_5H,
+            to: Square::SQ_5G,
+            promote: false,
+        }));
+    }
+
+    #[test]
+    fn is_pseudo_legal() {
+        for sfen in [
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+            "sfen lnsgkg1nl/1r5s1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL b Bb 1",
+            "sfen R8/2K1S1SSk/4B4/9/9/9/9/9/1L1L1L3 b RBGSNLP3g3n17p 1",
+            "sfen 9/9/9/9/9/7p1/7+r1/6KN1/4k1S2 b Pr2b4g3s3n4l16p 1",
+            "sfen 9/7pp/8k/7P1/7G1/9/9/9/9 b P2r2b3g4s4n4l14p 1",
+            "sfen lnsgkgsn1/1r5s1/pppppppp1/9/8l/9/PPPPPPPP1/1B5S1/LNSGKGSN1 b Ppl 1",
+        ] {
+            let pos = Position::new(PartialPosition::from_usi(sfen).expect("failed to parse"));
+            let pseudo_legal_moves = pos.pseudo_legal_moves();
+            let mut candidates = Vec::new();
+            for from in Square::all() {
+                for to in Square::all() {
+                    for promote in [false, true] {
+                        candidates.push(Move::Normal { from, to, promote });
+                    }
+                }
+            }
+            for to in Square::all() {
+                for c in Color::all() {
+                    for pk in Hand::all_hand_pieces() {
+                        candidates.push(Move::Drop {
+                            to,
+                            piece: Piece::new(pk, c),
+                        });
+                    }
+                }
+            }
+            for m in candidates {
+                assert_eq!(
+                    pseudo_legal_moves.contains(&m),
+                    pos.is_pseudo_legal(m),
+                    "{sfen}: {}",
+                    m.to_usi_owned()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn check_moves() {
+        // 開き王手
+        // P1 *  *  *  *  *  *  * -KE-OU
+        // P2 *  *  *  *  *  *  * -FU *
+        // P3 *  *  *  *  *  *  *  *  *
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  * +KE
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9 *  *  *  * +OU *  *  * +KY
+        // +
+        let pos = Position::new(
+            PartialPosition::from_usi("sfen 7nk/7p1/9/9/8N/9/9/9/4K3L b - 1")
+                .expect("failed to parse"),
+        );
+        let mut moves = pos
+            .check_moves()
+            .iter()
+            .map(|m| m.to_usi_owned())
+            .collect::<Vec<_>>();
+        moves.sort();
+        assert_eq!(vec!["1e2c", "1e2c+"], moves);
+
+        // 直接の王手と駒打ち
+        // P1 *  *  *  *  *  * -FU * -OU
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  *  *  * +FU * +KI
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  * +KY
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9 *  *  *  *  *  *  *  *  *
+        // P+00HI00KA00KY
+        // P-00AL
+        // +
+        let pos = Position::new(
+            PartialPosition::from_usi("sfen 6p1k/9/6P1G/9/8L/9/9/9/9 b RBLrb3g4s4n2l16p 1")
+                .expect("failed to parse"),
+        );
+        let mut moves = pos
+            .check_moves()
+            .iter()
+            .map(|m| m.to_usi_owned())
+            .collect::<Vec<_>>();
+        moves.sort();
+        assert_eq!(
+            vec!["1c1b", "1c2b", "1c2c", "B*2b", "L*1b", "R*1b", "R*2a"],
+            moves
+        );
+    }
+
     #[test]
     fn evasion_moves() {
         // TODO: add more cases
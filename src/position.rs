@@ -1,7 +1,13 @@
+use crate::accumulator::Accumulator;
 use crate::bitboard::Bitboard;
-use crate::tables::{ATTACK_TABLE, BETWEEN_TABLE};
+use crate::movegen::is_movable_rank;
+use crate::tables::{ATTACK_TABLE, BETWEEN_TABLE, PROMOTABLE};
 use crate::zobrist::{Key, ZOBRIST_TABLE};
+use arrayvec::ArrayVec;
 use shogi_core::{Color, Hand, Move, Piece, PieceKind, Square};
+#[cfg(feature = "usi")]
+use shogi_usi_parser::FromUsi;
+use std::fmt;
 
 /// Represents a state of the game with history. This provides the ability to do and undo moves.
 #[derive(Debug, Clone)]
@@ -15,6 +21,10 @@ impl Position {
     pub fn new(partial: shogi_core::PartialPosition) -> Position {
         let inner = PartialPosition::from(partial);
         let mut keys = (Key::ZERO, Key::ZERO);
+        // The key of the position reached by moves has `Key::COLOR` when White is to move.
+        if inner.side == Color::White {
+            keys.0 ^= Key::COLOR;
+        }
         for sq in Square::all() {
             if let Some(p) = inner.board[sq.array_index()] {
                 keys.0 ^= ZOBRIST_TABLE.board(sq, p);
@@ -32,8 +42,11 @@ impl Position {
         let checkers = AttackInfo::calculate_checkers(&inner);
         let state = State {
             keys,
+            last_move: None,
             captured: None,
             last_moved: None,
+            hands: inner.hands,
+            dirty_pieces: ArrayVec::new(),
             attack_info: AttackInfo::new(checkers, &inner),
         };
         Self {
@@ -67,7 +80,36 @@ impl Position {
     }
     #[inline(always)]
     pub fn in_check(&self) -> bool {
-        !self.checkers().is_empty()
+        !self.checkers_bitboard().is_empty()
+    }
+    /// Opponent's pieces giving check to the king of the side to move.
+    pub fn checkers(&self) -> shogi_core::Bitboard {
+        self.checkers_bitboard().into()
+    }
+    /// Pieces of the player `c` pinned to its own king by the opponent's sliding pieces.
+    pub fn pinned(&self, c: Color) -> shogi_core::Bitboard {
+        (self.pinned_bitboard(c) & self.player_bitboard(c)).into()
+    }
+    /// Pieces of the player `c` attacking the square.
+    pub fn attackers_to(&self, c: Color, sq: Square) -> shogi_core::Bitboard {
+        self.attackers_to_bitboard(c, sq, &self.occupied_bitboard())
+            .into()
+    }
+    /// Checks if the square is attacked by any piece of the player `c`.
+    pub fn is_attacked(&self, c: Color, sq: Square) -> bool {
+        !self
+            .attackers_to_bitboard(c, sq, &self.occupied_bitboard())
+            .is_empty()
+    }
+    /// Checks if the king of the player `c` can stand on the square without being attacked.
+    ///
+    /// Unlike [`Position::is_attacked`], the king itself doesn't block the opponent's sliding pieces.
+    pub fn is_safe_for_king(&self, c: Color, sq: Square) -> bool {
+        let mut occ = self.occupied_bitboard();
+        if let Some(king) = self.king_position(c) {
+            occ ^= Bitboard::single(king);
+        }
+        (self.attackers_to_bitboard(c.flip(), sq, &occ) & occ).is_empty()
     }
     pub fn is_check_move(&self, m: Move) -> bool {
         match m {
@@ -84,7 +126,7 @@ impl Position {
                 }
                 // 開き王手
                 let c = self.inner.side.flip();
-                if self.pinned(c).contains(from) {
+                if self.pinned_bitboard(c).contains(from) {
                     let sq = self.king_position(c).unwrap();
                     return !(BETWEEN_TABLE[sq.array_index()][from.array_index()].contains(to)
                         || BETWEEN_TABLE[sq.array_index()][to.array_index()].contains(from));
@@ -95,40 +137,48 @@ impl Position {
         }
     }
     pub fn do_move(&mut self, m: Move) {
+        self.do_move_with(m, &mut ());
+    }
+    /// Does the move, notifying the accumulator of the changes of pieces.
+    pub fn do_move_with<A: Accumulator>(&mut self, m: Move, acc: &mut A) {
         let c = self.side_to_move();
         let is_check = self.is_check_move(m);
         let captured = self.inner.piece_at(m.to());
         let last_moved;
+        let mut dirty_pieces = ArrayVec::new();
         let mut keys = self.state().keys;
         let checkers = match m {
             Move::Normal { from, to, promote } => {
                 let piece = self.inner.piece_at(from).unwrap();
                 last_moved = Some(piece);
-                if let Some(p) = captured {
+                let captured_to_hand = captured.map(|p| {
                     let pk = p.piece_kind();
                     let pk_unpromoted = pk.unpromote().unwrap_or(pk);
+                    let num = self.inner.hand_of_a_player(c).count(pk_unpromoted).unwrap();
                     // Update keys
                     keys.0 ^= ZOBRIST_TABLE.board(to, p);
-                    keys.1 ^= ZOBRIST_TABLE.hand(
-                        c,
-                        pk_unpromoted,
-                        self.inner.hand_of_a_player(c).count(pk_unpromoted).unwrap(),
-                    );
+                    keys.1 ^= ZOBRIST_TABLE.hand(c, pk_unpromoted, num);
                     // Update inner state
-                    self.inner.xor_piece(to, p);
-                    let hand = self.inner.hand_of_a_player_mut(c);
-                    *hand = hand.added(pk_unpromoted).unwrap();
-                }
+                    self.inner.remove_piece(to, p, acc);
+                    self.inner.add_to_hand(c, pk_unpromoted, acc);
+                    DirtyPiece {
+                        old: PieceLocation::Board(p, to),
+                        new: PieceLocation::Hand(c, pk_unpromoted, num + 1),
+                    }
+                });
                 let target_piece = if promote {
                     piece.promote().unwrap()
                 } else {
                     piece
                 };
                 // Update inner state
-                self.inner.xor_piece(from, piece);
-                self.inner.xor_piece(to, target_piece);
-                *self.inner.piece_at_mut(from) = None;
-                *self.inner.piece_at_mut(to) = Some(target_piece);
+                self.inner.remove_piece(from, piece, acc);
+                self.inner.add_piece(to, target_piece, acc);
+                dirty_pieces.push(DirtyPiece {
+                    old: PieceLocation::Board(piece, from),
+                    new: PieceLocation::Board(target_piece, to),
+                });
+                dirty_pieces.extend(captured_to_hand);
                 self.inner.side = c.flip();
                 // Update keys
                 keys.0 ^= ZOBRIST_TABLE.board(from, piece);
@@ -141,21 +191,21 @@ impl Position {
             }
             Move::Drop { to, piece } => {
                 last_moved = Some(piece);
+                let num = self
+                    .inner
+                    .hand_of_a_player(c)
+                    .count(piece.piece_kind())
+                    .unwrap();
                 // Update inner state
-                self.inner.xor_piece(to, piece);
-                *self.inner.piece_at_mut(to) = Some(piece);
-                let hand = self.inner.hand_of_a_player_mut(c);
-                *hand = hand.removed(piece.piece_kind()).unwrap();
+                self.inner.add_piece(to, piece, acc);
+                self.inner.remove_from_hand(c, piece.piece_kind(), acc);
+                dirty_pieces.push(DirtyPiece {
+                    old: PieceLocation::Hand(c, piece.piece_kind(), num),
+                    new: PieceLocation::Board(piece, to),
+                });
                 self.inner.side = c.flip();
                 // Update keys
-                keys.1 ^= ZOBRIST_TABLE.hand(
-                    c,
-                    piece.piece_kind(),
-                    self.inner
-                        .hand_of_a_player(c)
-                        .count(piece.piece_kind())
-                        .unwrap(),
-                );
+                keys.1 ^= ZOBRIST_TABLE.hand(c, piece.piece_kind(), num - 1);
                 keys.0 ^= ZOBRIST_TABLE.board(to, piece);
                 if is_check {
                     Bitboard::single(to)
@@ -168,12 +218,79 @@ impl Position {
         keys.0 ^= Key::COLOR;
         self.states.push(State {
             keys,
+            last_move: Some(m),
             captured,
             last_moved,
+            hands: self.inner.hands,
+            dirty_pieces,
             attack_info: AttackInfo::new(checkers, &self.inner),
         });
     }
+    /// Does the move after validating it. The position is left untouched if the move is illegal.
+    pub fn try_do_move(&mut self, m: Move) -> Result<(), MoveError> {
+        let c = self.side_to_move();
+        match m {
+            Move::Normal { from, to, promote } => {
+                let piece = self.piece_at(from).ok_or(MoveError::NoPieceAtSource)?;
+                if piece.color() != c {
+                    return Err(MoveError::WrongColor);
+                }
+                let pk = piece.piece_kind();
+                if self.player_bitboard(c).contains(to)
+                    || !ATTACK_TABLE
+                        .attack(pk, from, c, &self.occupied_bitboard())
+                        .contains(to)
+                {
+                    return Err(MoveError::Unreachable);
+                }
+                if promote {
+                    if pk.promote().is_none()
+                        || !(PROMOTABLE[from.array_index()][c.array_index()]
+                            || PROMOTABLE[to.array_index()][c.array_index()])
+                    {
+                        return Err(MoveError::IllegalPromotion);
+                    }
+                } else if !is_movable_rank(pk, to, c) {
+                    return Err(MoveError::IllegalPromotion);
+                }
+            }
+            Move::Drop { to, piece } => {
+                if piece.color() != c {
+                    return Err(MoveError::WrongColor);
+                }
+                if self.hand(c).count(piece.piece_kind()).unwrap_or_default() == 0 {
+                    return Err(MoveError::PieceNotInHand);
+                }
+                if self.piece_at(to).is_some() {
+                    return Err(MoveError::Unreachable);
+                }
+                if !self.is_valid_drop(piece.piece_kind(), to) {
+                    return Err(MoveError::IllegalDrop);
+                }
+            }
+        }
+        if !self.is_pseudo_legal(m) || !self.is_legal(m) {
+            return Err(MoveError::LeavesKingInCheck);
+        }
+        self.do_move(m);
+        Ok(())
+    }
+    /// Undoes the move. `m` must be the last move done.
     pub fn undo_move(&mut self, m: Move) {
+        self.undo_move_with(m, &mut ());
+    }
+    /// Undoes the move, notifying the accumulator of the changes of pieces. `m` must be the last move done.
+    pub fn undo_move_with<A: Accumulator>(&mut self, m: Move, acc: &mut A) {
+        debug_assert_eq!(Some(m), self.last_move(), "not the last move");
+        self.undo_with(acc);
+    }
+    /// Undoes the last move and returns it, or `None` if no moves have been done.
+    pub fn undo(&mut self) -> Option<Move> {
+        self.undo_with(&mut ())
+    }
+    /// Undoes the last move like [`Position::undo`], notifying the accumulator of the changes of pieces.
+    pub fn undo_with<A: Accumulator>(&mut self, acc: &mut A) -> Option<Move> {
+        let m = self.last_move()?;
         let c = self.side_to_move().flip();
         match m {
             Move::Normal {
@@ -182,29 +299,178 @@ impl Position {
                 promote: _,
             } => {
                 let last_moved = self.last_moved().unwrap();
-                let captured = self.captured();
-                if let Some(p_cap) = captured {
+                self.inner
+                    .remove_piece(to, self.inner.piece_at(to).unwrap(), acc);
+                self.inner.add_piece(from, last_moved, acc);
+                if let Some(p_cap) = self.captured() {
                     let pk = p_cap.piece_kind();
-                    let pk_unpromoted = pk.unpromote().unwrap_or(pk);
-                    self.inner.xor_piece(to, p_cap);
-                    let hand = self.inner.hand_of_a_player_mut(c);
-                    *hand = hand.removed(pk_unpromoted).unwrap();
+                    self.inner
+                        .remove_from_hand(c, pk.unpromote().unwrap_or(pk), acc);
+                    self.inner.add_piece(to, p_cap, acc);
                 }
-                self.inner.xor_piece(from, last_moved);
-                self.inner.xor_piece(to, self.inner.piece_at(to).unwrap());
-                *self.inner.piece_at_mut(from) = Some(last_moved);
-                *self.inner.piece_at_mut(to) = captured;
             }
             Move::Drop { to, piece } => {
-                self.inner.xor_piece(to, piece);
-                *self.inner.piece_at_mut(to) = None;
-                let hand = self.inner.hand_of_a_player_mut(c);
-                *hand = hand.added(piece.piece_kind()).unwrap();
+                self.inner.remove_piece(to, piece, acc);
+                self.inner.add_to_hand(c, piece.piece_kind(), acc);
             }
         }
         self.inner.side = c;
         self.inner.ply -= 1;
         self.states.pop();
+        Some(m)
+    }
+    /// Passes the turn without moving a piece, for null move pruning.
+    /// Returns [`MoveError::InCheck`] without passing if the side to move is in check.
+    pub fn do_null_move(&mut self) -> Result<(), MoveError> {
+        if self.in_check() {
+            return Err(MoveError::InCheck);
+        }
+        let mut keys = self.state().keys;
+        keys.0 ^= Key::COLOR;
+        self.inner.side = self.inner.side.flip();
+        self.inner.ply += 1;
+        // The new side to move can't be in check, since its last move can't have left its own king in check.
+        self.states.push(State {
+            keys,
+            last_move: None,
+            captured: None,
+            last_moved: None,
+            hands: self.inner.hands,
+            dirty_pieces: ArrayVec::new(),
+            attack_info: AttackInfo::new(Bitboard::empty(), &self.inner),
+        });
+        Ok(())
+    }
+    /// Undoes the null move done by [`Position::do_null_move`].
+    pub fn undo_null_move(&mut self) {
+        debug_assert!(self.states.len() > 1 && self.last_move().is_none());
+        self.inner.side = self.inner.side.flip();
+        self.inner.ply -= 1;
+        self.states.pop();
+    }
+    /// Returns the last move done, or `None` if no moves have been done.
+    #[inline(always)]
+    pub fn last_move(&self) -> Option<Move> {
+        self.state().last_move
+    }
+    /// Returns the piece captured by the last move, or `None` if it captured nothing.
+    #[inline(always)]
+    pub fn captured(&self) -> Option<Piece> {
+        self.state().captured
+    }
+    /// Returns the pieces changed by the last move: the moved piece first, then the captured piece if any.
+    /// It's empty if no moves have been done or the last move is a null move.
+    #[inline(always)]
+    pub fn last_dirty_pieces(&self) -> &[DirtyPiece] {
+        &self.state().dirty_pieces
+    }
+    /// Converts the current position to `shogi_core::PartialPosition`. The history is not included.
+    pub fn to_partial_position(&self) -> shogi_core::PartialPosition {
+        let mut partial = shogi_core::PartialPosition::empty();
+        for sq in Square::all() {
+            partial.piece_set(sq, self.piece_at(sq));
+        }
+        for c in Color::all() {
+            *partial.hand_of_a_player_mut(c) = self.hand(c);
+        }
+        partial.side_to_move_set(self.side_to_move());
+        // The ply is never 0, since it comes from a `shogi_core::PartialPosition`
+        let _ = partial.ply_set(self.ply());
+        partial
+    }
+    /// Creates a position from `shogi_core::Position` by replaying its moves from the initial position,
+    /// so that the history is available for undoing moves and detecting repetitions.
+    pub fn from_core_position(core: &shogi_core::Position) -> Result<Position, MoveError> {
+        let mut pos = Position::new(core.initial_position().clone());
+        for &m in core.moves() {
+            pos.try_do_move(m)?;
+        }
+        Ok(pos)
+    }
+    /// Converts to `shogi_core::Position` with the moves in the history.
+    /// The history before the last null move is not included.
+    pub fn to_core_position(&self) -> shogi_core::Position {
+        let mut pos = self.clone();
+        let mut moves = Vec::new();
+        while let Some(m) = pos.undo() {
+            moves.push(m);
+        }
+        let mut core = shogi_core::Position::arbitrary_position(pos.to_partial_position());
+        for m in moves.into_iter().rev() {
+            core.make_move(m).expect("invalid move in the history");
+        }
+        core
+    }
+    /// Returns the SFEN representation of the current position, without the leading `sfen`.
+    pub fn to_sfen(&self) -> String {
+        self.to_partial_position().to_sfen_owned()
+    }
+    /// Parses the SFEN representation of a position. The leading `sfen`, or `startpos`, is also accepted.
+    #[cfg(feature = "usi")]
+    pub fn from_sfen(sfen: &str) -> Result<Position, shogi_usi_parser::Error> {
+        let partial = if sfen.starts_with("sfen") || sfen.starts_with("startpos") {
+            shogi_core::PartialPosition::from_usi(sfen)?
+        } else {
+            shogi_core::PartialPosition::from_usi(&format!("sfen {sfen}"))?
+        };
+        Ok(Position::new(partial))
+    }
+    /// Checks the repetition (千日手) of the current position in the history.
+    ///
+    /// [`RepetitionStatus::Draw`], [`RepetitionStatus::Win`] and [`RepetitionStatus::Lose`] are reported
+    /// when the same position occurs for the fourth time, and [`RepetitionStatus::Superior`] and
+    /// [`RepetitionStatus::Inferior`] when the same board has occurred with different hands.
+    /// The history before a null move is not taken into account.
+    pub fn repetition_status(&self) -> RepetitionStatus {
+        self.repetition_status_since(0)
+    }
+    /// Checks the repetition like [`Position::repetition_status`], but the same position occurred within
+    /// the last `plies` plies is a repetition at its second occurrence, for the search which needn't repeat it further.
+    pub(crate) fn repetition_status_since(&self, plies: usize) -> RepetitionStatus {
+        let n = self.states.len() - 1;
+        let curr = &self.states[n];
+        let c = self.side_to_move();
+        let mut count = 0;
+        let mut ret = RepetitionStatus::None;
+        for i in (0..n).rev() {
+            if self.states[i + 1].last_move.is_none() {
+                break;
+            }
+            let state = &self.states[i];
+            if state.keys.0.value() != curr.keys.0.value() {
+                continue;
+            }
+            if state.keys.1.value() == curr.keys.1.value() {
+                count += 1;
+                if count == REPETITION_COUNT - 1 || n - i <= plies {
+                    // 連続王手の千日手
+                    let in_check = |j: usize| !self.states[j].attack_info.checkers().is_empty();
+                    if (i + 1..=n).rev().step_by(2).all(in_check) {
+                        return RepetitionStatus::Win;
+                    }
+                    if (i + 1..n).rev().step_by(2).all(in_check) {
+                        return RepetitionStatus::Lose;
+                    }
+                    return RepetitionStatus::Draw;
+                }
+            } else if ret == RepetitionStatus::None {
+                // 優等局面・劣等局面
+                let (prev, curr) = (state.hands[c.array_index()], curr.hands[c.array_index()]);
+                if Hand::all_hand_pieces().all(|pk| curr.count(pk) >= prev.count(pk)) {
+                    ret = RepetitionStatus::Superior;
+                } else if Hand::all_hand_pieces().all(|pk| curr.count(pk) <= prev.count(pk)) {
+                    ret = RepetitionStatus::Inferior;
+                }
+            }
+        }
+        ret
+    }
+    /// Copies the current position without the history, to try moves in a query.
+    pub(crate) fn without_history(&self) -> Position {
+        Position {
+            inner: self.inner.clone(),
+            states: vec![self.state().clone()],
+        }
     }
     #[inline(always)]
     pub(crate) fn player_bitboard(&self, c: Color) -> Bitboard {
@@ -228,22 +494,22 @@ impl Position {
         self.inner.king_position(c)
     }
     #[inline(always)]
-    pub(crate) fn captured(&self) -> Option<Piece> {
-        self.state().captured
-    }
-    #[inline(always)]
     pub(crate) fn last_moved(&self) -> Option<Piece> {
         self.state().last_moved
     }
     #[inline(always)]
-    pub(crate) fn checkers(&self) -> Bitboard {
+    pub(crate) fn checkers_bitboard(&self) -> Bitboard {
         self.state().attack_info.checkers()
     }
     #[inline(always)]
-    pub(crate) fn pinned(&self, c: Color) -> Bitboard {
+    pub(crate) fn pinned_bitboard(&self, c: Color) -> Bitboard {
         self.state().attack_info.pinned(c)
     }
     #[inline(always)]
+    pub(crate) fn checkables(&self, pk: PieceKind) -> Bitboard {
+        self.state().attack_info.checkables(pk)
+    }
+    #[inline(always)]
     fn state(&self) -> &State {
         self.states.last().expect("empty states")
     }
@@ -259,6 +525,87 @@ impl Default for Position {
     }
 }
 
+impl From<&Position> for shogi_core::PartialPosition {
+    fn from(pos: &Position) -> Self {
+        pos.to_partial_position()
+    }
+}
+
+/// The number of occurrences of the same position to be a repetition (千日手).
+const REPETITION_COUNT: usize = 4;
+
+/// Represents the repetition status of a position, from the point of view of the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepetitionStatus {
+    /// No repetition
+    None,
+    /// Draw by repetition
+    Draw,
+    /// Win, since the opponent has given perpetual check
+    Win,
+    /// Lose, since the side to move has given perpetual check
+    Lose,
+    /// The same board has occurred with a hand inferior to the current one
+    Superior,
+    /// The same board has occurred with a hand superior to the current one
+    Inferior,
+}
+
+/// Represents the reason why a move can't be done in [`Position::try_do_move`],
+/// or a null move in [`Position::do_null_move`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// No piece is at the source square
+    NoPieceAtSource,
+    /// The piece to move doesn't belong to the side to move
+    WrongColor,
+    /// The piece can't reach the destination square
+    Unreachable,
+    /// The piece can't (or must) promote with the move
+    IllegalPromotion,
+    /// The piece to drop isn't in hand
+    PieceNotInHand,
+    /// The drop violates the rules: dead ranks, nifu or uchifuzume
+    IllegalDrop,
+    /// The move leaves the king in check
+    LeavesKingInCheck,
+    /// The side to move is in check, so it can't pass with a null move
+    InCheck,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MoveError::NoPieceAtSource => "no piece at the source square",
+            MoveError::WrongColor => "the piece doesn't belong to the side to move",
+            MoveError::Unreachable => "the piece can't reach the destination square",
+            MoveError::IllegalPromotion => "illegal promotion",
+            MoveError::PieceNotInHand => "the piece isn't in hand",
+            MoveError::IllegalDrop => "illegal drop",
+            MoveError::LeavesKingInCheck => "the move leaves the king in check",
+            MoveError::InCheck => "the side to move is in check",
+        })
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// Represents where a piece is, as a feature of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PieceLocation {
+    /// The piece on the square
+    Board(Piece, Square),
+    /// The `n`-th piece of the kind, counted from 1, in the hand of the player
+    Hand(Color, PieceKind, u8),
+}
+
+/// Represents a piece changed by a move, from `old` to `new`, for differential updates of evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirtyPiece {
+    pub old: PieceLocation,
+    pub new: PieceLocation,
+}
+
 /// Represents a state of a single position of a game.
 #[derive(Clone, Debug)]
 pub(crate) struct PartialPosition {
@@ -278,21 +625,41 @@ impl PartialPosition {
         self.player_bb[c.array_index()] ^= single;
         self.piece_bb[pk.array_index()] ^= single;
     }
+    /// Puts the piece on the empty square.
     #[inline(always)]
-    fn piece_at(&self, sq: Square) -> Option<Piece> {
-        self.board[sq.array_index()]
+    fn add_piece<A: Accumulator>(&mut self, sq: Square, p: Piece, acc: &mut A) {
+        self.xor_piece(sq, p);
+        self.board[sq.array_index()] = Some(p);
+        acc.on_add(p, sq);
     }
+    /// Removes the piece from the square.
     #[inline(always)]
-    fn piece_at_mut(&mut self, sq: Square) -> &mut Option<Piece> {
-        &mut self.board[sq.array_index()]
+    fn remove_piece<A: Accumulator>(&mut self, sq: Square, p: Piece, acc: &mut A) {
+        self.xor_piece(sq, p);
+        self.board[sq.array_index()] = None;
+        acc.on_remove(p, sq);
     }
     #[inline(always)]
-    fn hand_of_a_player(&self, c: Color) -> Hand {
-        self.hands[c.array_index()]
+    fn add_to_hand<A: Accumulator>(&mut self, c: Color, pk: PieceKind, acc: &mut A) {
+        let hand = &mut self.hands[c.array_index()];
+        let before = hand.count(pk).unwrap();
+        *hand = hand.added(pk).unwrap();
+        acc.on_hand_change(c, pk, before, before + 1);
+    }
+    #[inline(always)]
+    fn remove_from_hand<A: Accumulator>(&mut self, c: Color, pk: PieceKind, acc: &mut A) {
+        let hand = &mut self.hands[c.array_index()];
+        let before = hand.count(pk).unwrap();
+        *hand = hand.removed(pk).unwrap();
+        acc.on_hand_change(c, pk, before, before - 1);
     }
     #[inline(always)]
-    fn hand_of_a_player_mut(&mut self, c: Color) -> &mut Hand {
-        &mut self.hands[c.array_index()]
+    fn piece_at(&self, sq: Square) -> Option<Piece> {
+        self.board[sq.array_index()]
+    }
+    #[inline(always)]
+    fn hand_of_a_player(&self, c: Color) -> Hand {
+        self.hands[c.array_index()]
     }
     #[inline(always)]
     fn occupied_bitboard(&self) -> Bitboard {
@@ -338,10 +705,16 @@ impl From<shogi_core::PartialPosition> for PartialPosition {
 struct State {
     /// Zobrist hashes for (board ^ side, hand)
     keys: (Key, Key),
+    /// Last move
+    last_move: Option<Move>,
     /// Piece captured on the last move
     captured: Option<Piece>,
     /// Last moved piece
     last_moved: Option<Piece>,
+    /// Hands of both players, to compare positions in repetition
+    hands: [Hand; Color::NUM],
+    /// Pieces changed by the last move
+    dirty_pieces: ArrayVec<DirtyPiece, 2>,
     attack_info: AttackInfo,
 }
 
@@ -440,6 +813,10 @@ impl AttackInfo {
     pub fn checkable(&self, pk: PieceKind, sq: Square) -> bool {
         self.checkables[pk.array_index()].contains(sq)
     }
+    #[inline(always)]
+    pub fn checkables(&self, pk: PieceKind) -> Bitboard {
+        self.checkables[pk.array_index()]
+    }
 }
 
 #[cfg(test)]
@@ -547,10 +924,13 @@ mod tests {
         assert_eq!(Color::White, pos.side_to_move());
         assert_eq!(6, pos.ply());
         assert_eq!(true, pos.in_check());
+        assert_eq!(moves.last().copied(), pos.last_move());
         // revert to default position
         for &m in moves.iter().rev() {
-            pos.undo_move(m);
+            assert_eq!(Some(m), pos.undo());
         }
+        assert_eq!(None, pos.last_move());
+        assert_eq!(None, pos.undo());
         let default = Position::default();
         assert!(Square::all().all(|sq| pos.piece_at(sq) == default.piece_at(sq)));
         assert_eq!(Color::Black, pos.side_to_move());
@@ -558,6 +938,363 @@ mod tests {
         assert_eq!(false, pos.in_check());
     }
 
+    #[test]
+    fn do_undo_null_move() {
+        let mut pos = Position::default();
+        let m = Move::Normal {
+            from: Square::SQ_7G,
+            to: Square::SQ_7F,
+            promote: false,
+        };
+        pos.do_move(m);
+        let key = pos.key();
+        let legal_moves = pos.legal_moves();
+        assert_eq!(Ok(()), pos.do_null_move());
+        assert_eq!(Color::Black, pos.side_to_move());
+        assert_eq!(3, pos.ply());
+        assert_ne!(key, pos.key());
+        assert!(!pos.in_check());
+        let expected = Position::new(
+            PartialPosition::from_usi(
+                "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3",
+            )
+            .expect("failed to parse"),
+        );
+        assert_eq!(expected.key(), pos.key());
+        assert_eq!(expected.legal_moves(), pos.legal_moves());
+        pos.undo_null_move();
+        assert_eq!(Color::White, pos.side_to_move());
+        assert_eq!(2, pos.ply());
+        assert_eq!(key, pos.key());
+        assert_eq!(legal_moves, pos.legal_moves());
+        assert_eq!(Some(m), pos.last_move());
+    }
+
+    #[test]
+    fn null_move_in_check() {
+        // P1 *  *  *  *  *  *  *  * -OU
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  *  *  *  *  *  *
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  * -KI *  *  *  *
+        // P9 *  *  *  * +OU *  *  *  *
+        // P-00AL
+        // +
+        let mut pos = Position::new(
+            PartialPosition::from_usi("sfen 8k/9/9/9/9/9/9/4g4/4K4 b 2r2b3g4s4n4l18p 1")
+                .expect("failed to parse"),
+        );
+        let key = pos.key();
+        assert_eq!(Err(MoveError::InCheck), pos.do_null_move());
+        assert_eq!(Color::Black, pos.side_to_move());
+        assert_eq!(1, pos.ply());
+        assert_eq!(key, pos.key());
+    }
+
+    #[test]
+    fn try_do_move() {
+        // P1 *  *  *  *  *  *  *  * -OU
+        // P2 *  *  *  *  *  *  * +FU *
+        // P3 *  *  *  *  *  *  *  *  *
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  * -HI *  *  *  *
+        // P8 *  *  *  * +KI *  *  *  *
+        // P9 *  *  *  * +OU *  *  *  *
+        // P+00FU00KE
+        // P-00AL
+        // +
+        let mut pos = Position::new(
+            PartialPosition::from_usi("sfen 8k/7P1/9/9/9/9/4r4/4G4/4K4 b NPr2b3g4s3n4l16p 1")
+                .expect("failed to parse"),
+        );
+        let test_cases = [
+            (
+                Move::Normal {
+                    from: Square::SQ_5E,
+                    to: Square::SQ_5D,
+                    promote: false,
+                },
+                MoveError::NoPieceAtSource,
+            ),
+            (
+                Move::Normal {
+                    from: Square::SQ_5G,
+                    to: Square::SQ_5H,
+                    promote: false,
+                },
+                MoveError::WrongColor,
+            ),
+            (
+                Move::Normal {
+                    from: Square::SQ_5H,
+                    to: Square::SQ_5F,
+                    promote: false,
+                },
+                MoveError::Unreachable,
+            ),
+            (
+                Move::Normal {
+                    from: Square::SQ_5H,
+                    to: Square::SQ_5G,
+                    promote: true,
+                },
+                MoveError::IllegalPromotion,
+            ),
+            (
+                Move::Normal {
+                    from: Square::SQ_2B,
+                    to: Square::SQ_2A,
+                    promote: false,
+                },
+                MoveError::IllegalPromotion,
+            ),
+            (
+                Move::Drop {
+                    to: Square::SQ_5E,
+                    piece: Piece::B_S,
+                },
+                MoveError::PieceNotInHand,
+            ),
+            (
+                Move::Drop {
+                    to: Square::SQ_5E,
+                    piece: Piece::W_P,
+                },
+                MoveError::WrongColor,
+            ),
+            (
+                Move::Drop {
+                    to: Square::SQ_3B,
+                    piece: Piece::B_N,
+                },
+                MoveError::IllegalDrop,
+            ),
+            (
+                Move::Drop {
+                    to: Square::SQ_2E,
+                    piece: Piece::B_P,
+                },
+                MoveError::IllegalDrop,
+            ),
+            (
+                Move::Normal {
+                    from: Square::SQ_5H,
+                    to: Square::SQ_4H,
+                    promote: false,
+                },
+                MoveError::LeavesKingInCheck,
+            ),
+        ];
+        let key = pos.key();
+        for (m, expected) in test_cases {
+            assert_eq!(Err(expected), pos.try_do_move(m), "{m:?}");
+            assert_eq!(key, pos.key());
+            assert_eq!(1, pos.ply());
+        }
+        let m = Move::Normal {
+            from: Square::SQ_5H,
+            to: Square::SQ_5G,
+            promote: false,
+        };
+        assert_eq!(Ok(()), pos.try_do_move(m));
+        assert_eq!(Some(Piece::B_G), pos.piece_at(Square::SQ_5G));
+    }
+
+    #[test]
+    fn try_do_move_in_check() {
+        // P1 *  *  *  *  *  *  * -KE-OU
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  *  *  *  * +KI *
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  * -HI *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7+FU *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9 *  *  *  * +OU *  *  *  *
+        // P+00KI00KE00FU
+        // P-00AL
+        // +
+        let mut pos = Position::new(
+            PartialPosition::from_usi("sfen 7nk/9/7G1/9/4r4/9/P8/9/4K4 b GNPr2b2g4s2n4l16p 1")
+                .expect("failed to parse"),
+        );
+        assert!(pos.in_check());
+        let test_cases = [
+            (Square::SQ_5G, Piece::W_P, MoveError::WrongColor),
+            (Square::SQ_5G, Piece::B_S, MoveError::PieceNotInHand),
+            (Square::SQ_5E, Piece::B_G, MoveError::Unreachable),
+            // Dead rank
+            (Square::SQ_5B, Piece::B_N, MoveError::IllegalDrop),
+            // 二歩
+            (Square::SQ_9E, Piece::B_P, MoveError::IllegalDrop),
+            // 打ち歩詰め
+            (Square::SQ_1B, Piece::B_P, MoveError::IllegalDrop),
+            (Square::SQ_4H, Piece::B_G, MoveError::LeavesKingInCheck),
+        ];
+        let key = pos.key();
+        for (to, piece, expected) in test_cases {
+            let m = Move::Drop { to, piece };
+            assert_eq!(Err(expected), pos.try_do_move(m), "{m:?}");
+            assert_eq!(key, pos.key());
+        }
+        let m = Move::Drop {
+            to: Square::SQ_5G,
+            piece: Piece::B_G,
+        };
+        assert_eq!(Ok(()), pos.try_do_move(m));
+        assert_eq!(Some(Piece::B_G), pos.piece_at(Square::SQ_5G));
+    }
+
+    #[test]
+    fn repetition_status() {
+        fn normal(from: Square, to: Square) -> Move {
+            Move::Normal {
+                from,
+                to,
+                promote: false,
+            }
+        }
+
+        // Draw
+        {
+            let mut pos = Position::default();
+            let moves = [
+                normal(Square::SQ_2H, Square::SQ_3H),
+                normal(Square::SQ_8B, Square::SQ_7B),
+                normal(Square::SQ_3H, Square::SQ_2H),
+                normal(Square::SQ_7B, Square::SQ_8B),
+            ];
+            for i in 0..3 {
+                assert_eq!(RepetitionStatus::None, pos.repetition_status());
+                for &m in &moves {
+                    pos.do_move(m);
+                }
+                if i < 2 {
+                    assert_eq!(RepetitionStatus::None, pos.repetition_status());
+                }
+            }
+            assert_eq!(RepetitionStatus::Draw, pos.repetition_status());
+            pos.undo();
+            assert_eq!(RepetitionStatus::None, pos.repetition_status());
+        }
+        // Perpetual check
+        // P1 *  *  *  *  *  *  *  * -OU
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  *  *  *  *  * +HI
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9+OU *  *  *  *  *  *  *  *
+        // P-00AL
+        // -
+        {
+            let mut pos = Position::new(
+                PartialPosition::from_usi("sfen 8k/9/8R/9/9/9/9/9/K8 w r2b4g4s4n4l18p 1")
+                    .expect("failed to parse"),
+            );
+            let moves = [
+                normal(Square::SQ_1A, Square::SQ_2A),
+                normal(Square::SQ_1C, Square::SQ_2C),
+                normal(Square::SQ_2A, Square::SQ_1A),
+                normal(Square::SQ_2C, Square::SQ_1C),
+            ];
+            for _ in 0..3 {
+                for &m in &moves {
+                    pos.do_move(m);
+                }
+            }
+            assert_eq!(RepetitionStatus::Win, pos.repetition_status());
+            pos.do_move(moves[0]);
+            assert_eq!(RepetitionStatus::Lose, pos.repetition_status());
+        }
+        // Superior / Inferior
+        // P1 *  *  *  * -OU *  *  *  *
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  *  *  *  *  * -FU
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  * +HI
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9+OU *  *  *  *  *  *  *  *
+        // P-00AL
+        // +
+        {
+            let mut pos = Position::new(
+                PartialPosition::from_usi("sfen 4k4/9/8p/9/8R/9/9/9/K8 b r2b4g4s4n4l17p 1")
+                    .expect("failed to parse"),
+            );
+            for m in [
+                normal(Square::SQ_1E, Square::SQ_1C),
+                normal(Square::SQ_5A, Square::SQ_5B),
+                normal(Square::SQ_1C, Square::SQ_1E),
+                Move::Drop {
+                    to: Square::SQ_1C,
+                    piece: Piece::W_P,
+                },
+                normal(Square::SQ_9I, Square::SQ_9H),
+                normal(Square::SQ_5B, Square::SQ_4B),
+                normal(Square::SQ_9H, Square::SQ_9I),
+                normal(Square::SQ_4B, Square::SQ_5A),
+            ] {
+                assert_eq!(RepetitionStatus::None, pos.repetition_status());
+                pos.do_move(m);
+            }
+            assert_eq!(RepetitionStatus::Superior, pos.repetition_status());
+            pos.do_move(normal(Square::SQ_1E, Square::SQ_1C));
+            assert_eq!(RepetitionStatus::Inferior, pos.repetition_status());
+        }
+    }
+
+    #[test]
+    fn repetition_status_since() {
+        fn normal(from: Square, to: Square) -> Move {
+            Move::Normal {
+                from,
+                to,
+                promote: false,
+            }
+        }
+
+        // Draw
+        let mut pos = Position::default();
+        for m in [
+            normal(Square::SQ_2H, Square::SQ_3H),
+            normal(Square::SQ_8B, Square::SQ_7B),
+            normal(Square::SQ_3H, Square::SQ_2H),
+            normal(Square::SQ_7B, Square::SQ_8B),
+        ] {
+            pos.do_move(m);
+        }
+        assert_eq!(RepetitionStatus::None, pos.repetition_status());
+        assert_eq!(RepetitionStatus::None, pos.repetition_status_since(3));
+        assert_eq!(RepetitionStatus::Draw, pos.repetition_status_since(4));
+
+        // Perpetual check
+        let mut pos = Position::new(
+            PartialPosition::from_usi("sfen 8k/9/8R/9/9/9/9/9/K8 w r2b4g4s4n4l18p 1")
+                .expect("failed to parse"),
+        );
+        for m in [
+            normal(Square::SQ_1A, Square::SQ_2A),
+            normal(Square::SQ_1C, Square::SQ_2C),
+            normal(Square::SQ_2A, Square::SQ_1A),
+            normal(Square::SQ_2C, Square::SQ_1C),
+        ] {
+            pos.do_move(m);
+        }
+        assert_eq!(RepetitionStatus::Win, pos.repetition_status_since(4));
+        pos.do_move(normal(Square::SQ_1A, Square::SQ_2A));
+        assert_eq!(RepetitionStatus::Lose, pos.repetition_status_since(4));
+    }
+
     #[test]
     fn perft() {
         fn perft(pos: &mut Position, depth: usize) -> u64 {
@@ -705,4 +1442,263 @@ mod tests {
             assert_eq!(expected, pos.is_check_move(m));
         }
     }
+
+    #[test]
+    fn attack_queries() {
+        // P1 *  *  *  * -OU *  *  *  *
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  * -HI *  *  *  *
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  * -KA
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  * +KI *  *  *  *
+        // P9 *  *  *  * +OU *  *  *  *
+        // +
+        let pos = Position::new(
+            PartialPosition::from_usi("sfen 4k4/9/4r4/9/8b/9/9/4G4/4K4 b - 1")
+                .expect("failed to parse"),
+        );
+        assert_eq!(
+            vec![Square::SQ_1E],
+            pos.checkers().into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![Square::SQ_5H],
+            pos.pinned(Color::Black).into_iter().collect::<Vec<_>>()
+        );
+        assert!(pos.pinned(Color::White).is_empty());
+        assert_eq!(
+            vec![Square::SQ_5C],
+            pos.attackers_to(Color::White, Square::SQ_5E)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, pos.attackers_to(Color::Black, Square::SQ_5G).count());
+        assert_eq!(2, pos.attackers_to(Color::Black, Square::SQ_4H).count());
+        assert!(pos.is_attacked(Color::White, Square::SQ_4H));
+        assert!(!pos.is_attacked(Color::White, Square::SQ_6I));
+        assert!(pos.is_safe_for_king(Color::Black, Square::SQ_6I));
+        assert!(!pos.is_safe_for_king(Color::Black, Square::SQ_4H));
+
+        // P1 *  *  *  * -OU *  *  *  *
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  *  *  *  *  *  *
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9 * +OU *  *  *  *  *  * -HI
+        // +
+        let pos = Position::new(
+            PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/1K6r b - 1")
+                .expect("failed to parse"),
+        );
+        assert!(pos.in_check());
+        assert!(!pos.is_attacked(Color::White, Square::SQ_9I));
+        assert!(!pos.is_safe_for_king(Color::Black, Square::SQ_9I));
+        assert!(pos.is_safe_for_king(Color::Black, Square::SQ_8H));
+    }
+
+    #[test]
+    #[cfg(feature = "usi")]
+    fn to_sfen() {
+        let test_cases = [
+            "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+            // from maximum moves
+            "R8/2K1S1SSk/4B4/9/9/9/9/9/1L1L1L3 b RBGSNLP3g3n17p 1",
+            "8l/1l+R2P3/p2pBG1pp/kps1p4/Nn1P2G2/P1P1P2PP/1PS6/1KSG3+r1/LN2+p3L w Sbgn3p 124",
+        ];
+        for sfen in test_cases {
+            let pos = Position::from_sfen(sfen).expect("failed to parse");
+            assert_eq!(sfen, pos.to_sfen());
+            let partial = shogi_core::PartialPosition::from(&pos);
+            assert_eq!(sfen, partial.to_sfen_owned());
+            assert_eq!(
+                pos.to_sfen(),
+                Position::from_sfen(&format!("sfen {sfen}"))
+                    .expect("failed to parse")
+                    .to_sfen()
+            );
+        }
+        assert_eq!(
+            Position::default().to_sfen(),
+            Position::from_sfen("startpos")
+                .expect("failed to parse")
+                .to_sfen()
+        );
+        assert!(Position::from_sfen("invalid").is_err());
+    }
+
+    #[test]
+    fn to_partial_position() {
+        let sfen = "sfen R8/2K1S1SSk/4B4/9/9/9/9/9/1L1L1L3 b RBGSNLP3g3n17p 1";
+        let mut partial = PartialPosition::from_usi(sfen).expect("failed to parse");
+        let mut pos = Position::new(partial.clone());
+        // Each move is followed in both positions, and they must agree on SFEN
+        for _ in 0..20 {
+            let moves = pos.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            let m = moves[moves.len() / 2];
+            pos.do_move(m);
+            partial.make_move(m).expect("illegal move");
+            assert_eq!(partial.to_sfen_owned(), pos.to_sfen());
+            let converted = pos.to_partial_position();
+            assert_eq!(partial.side_to_move(), converted.side_to_move());
+            assert_eq!(partial.ply(), converted.ply());
+            for sq in Square::all() {
+                assert_eq!(partial.piece_at(sq), converted.piece_at(sq));
+            }
+            for c in Color::all() {
+                assert_eq!(partial.hand_of_a_player(c), converted.hand_of_a_player(c));
+            }
+        }
+        // Round trip of the position after moves
+        let restored = Position::new(pos.to_partial_position());
+        assert_eq!(pos.key(), restored.key());
+        assert_eq!(pos.to_sfen(), restored.to_sfen());
+    }
+
+    #[test]
+    fn key_side_to_move() {
+        let mut pos = Position::default();
+        pos.do_move(Move::Normal {
+            from: Square::SQ_7G,
+            to: Square::SQ_7F,
+            promote: false,
+        });
+        let created = Position::new(
+            PartialPosition::from_usi(&format!("sfen {}", pos.to_sfen())).expect("failed to parse"),
+        );
+        assert_eq!(Color::White, created.side_to_move());
+        assert_eq!(pos.key(), created.key());
+    }
+
+    #[test]
+    fn from_core_position() {
+        let core = shogi_core::Position::from_usi(
+            "startpos moves 2h5h 8b5b 5h2h 5b8b 2h5h 8b5b 5h2h 5b8b 2h5h 8b5b 5h2h 5b8b",
+        )
+        .expect("failed to parse");
+        let mut pos = Position::from_core_position(&core).expect("illegal move");
+        assert_eq!(core.inner().to_sfen_owned(), pos.to_sfen());
+        assert_eq!(RepetitionStatus::Draw, pos.repetition_status());
+
+        let converted = pos.to_core_position();
+        assert_eq!(core.initial_position(), converted.initial_position());
+        assert_eq!(core.moves(), converted.moves());
+        assert_eq!(
+            core.inner().to_sfen_owned(),
+            converted.inner().to_sfen_owned()
+        );
+
+        for &m in core.moves().iter().rev() {
+            assert_eq!(Some(m), pos.undo());
+        }
+        assert_eq!(None, pos.undo());
+        assert_eq!(Position::default().to_sfen(), pos.to_sfen());
+
+        // The history before a null move is not included
+        let mut pos = Position::from_core_position(
+            &shogi_core::Position::from_usi("startpos moves 7g7f 3c3d").expect("failed to parse"),
+        )
+        .expect("illegal move");
+        assert_eq!(Ok(()), pos.do_null_move());
+        pos.do_move(Move::Normal {
+            from: Square::SQ_2C,
+            to: Square::SQ_2D,
+            promote: false,
+        });
+        let converted = pos.to_core_position();
+        assert_eq!(1, converted.moves().len());
+        assert_eq!(pos.to_sfen(), converted.inner().to_sfen_owned());
+
+        // Illegal moves are rejected
+        let core = shogi_core::Position::from_usi("startpos moves 7g7f 3c3d 8h2b")
+            .expect("failed to parse");
+        assert!(Position::from_core_position(&core).is_ok());
+        let core = shogi_core::Position::from_usi("startpos moves 7g7f 3c3d 2h2d")
+            .expect("failed to parse");
+        assert_eq!(
+            Some(MoveError::Unreachable),
+            Position::from_core_position(&core).err()
+        );
+    }
+
+    #[test]
+    fn last_dirty_pieces() {
+        let mut pos = Position::default();
+        assert!(pos.last_dirty_pieces().is_empty());
+        let moves = [
+            Move::Normal {
+                from: Square::SQ_7G,
+                to: Square::SQ_7F,
+                promote: false,
+            },
+            Move::Normal {
+                from: Square::SQ_3C,
+                to: Square::SQ_3D,
+                promote: false,
+            },
+            Move::Normal {
+                from: Square::SQ_8H,
+                to: Square::SQ_2B,
+                promote: true,
+            },
+            Move::Normal {
+                from: Square::SQ_3A,
+                to: Square::SQ_2B,
+                promote: false,
+            },
+            Move::Drop {
+                to: Square::SQ_4E,
+                piece: Piece::B_B,
+            },
+        ];
+        for &m in &moves {
+            pos.do_move(m);
+        }
+        assert_eq!(
+            &[DirtyPiece {
+                old: PieceLocation::Hand(Color::Black, PieceKind::Bishop, 1),
+                new: PieceLocation::Board(Piece::B_B, Square::SQ_4E),
+            }],
+            pos.last_dirty_pieces()
+        );
+        assert_eq!(None, pos.captured());
+        pos.undo();
+        assert_eq!(Some(Piece::B_PB), pos.captured());
+        assert_eq!(
+            &[
+                DirtyPiece {
+                    old: PieceLocation::Board(Piece::W_S, Square::SQ_3A),
+                    new: PieceLocation::Board(Piece::W_S, Square::SQ_2B),
+                },
+                DirtyPiece {
+                    old: PieceLocation::Board(Piece::B_PB, Square::SQ_2B),
+                    new: PieceLocation::Hand(Color::White, PieceKind::Bishop, 1),
+                },
+            ],
+            pos.last_dirty_pieces()
+        );
+        pos.undo();
+        assert_eq!(
+            &[
+                DirtyPiece {
+                    old: PieceLocation::Board(Piece::B_B, Square::SQ_8H),
+                    new: PieceLocation::Board(Piece::B_PB, Square::SQ_2B),
+                },
+                DirtyPiece {
+                    old: PieceLocation::Board(Piece::W_B, Square::SQ_2B),
+                    new: PieceLocation::Hand(Color::Black, PieceKind::Bishop, 1),
+                },
+            ],
+            pos.last_dirty_pieces()
+        );
+        assert_eq!(Ok(()), pos.do_null_move());
+        assert!(pos.last_dirty_pieces().is_empty());
+    }
 }
@@ -0,0 +1,506 @@
+//! Alpha-beta search with iterative deepening, generic over the evaluation function.
+
+use crate::tt::{Bound, TranspositionTable};
+use crate::{Accumulator, MaterialPst, PieceValues, Position, RepetitionStatus};
+use shogi_core::{Move, PieceKind};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Maximum depth of the search in plies, including extensions and quiescence search.
+pub const MAX_PLY: usize = 128;
+/// Score of being checkmated now. Mate in `n` plies is `MATE - n`, and being mated in `n` plies is `-(MATE - n)`.
+pub const MATE: i32 = 30000;
+const INFINITE: i32 = MATE + 1;
+
+/// Checks if the score is a mate score, either of winning or losing.
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() > MATE - MAX_PLY as i32
+}
+
+/// Evaluation function for [`Searcher`].
+///
+/// The evaluator is notified of the changes of pieces on every move as an [`Accumulator`],
+/// so it can update its evaluation incrementally.
+pub trait Evaluator: Accumulator {
+    /// Called at the start of the search, to initialize the incremental state for the root position.
+    fn init(&mut self, _pos: &Position) {}
+    /// Score of the position from the viewpoint of the side to move, in the range of `-(MATE - MAX_PLY)..=MATE - MAX_PLY`.
+    fn evaluate(&mut self, pos: &Position) -> i32;
+}
+
+impl Evaluator for MaterialPst {
+    fn init(&mut self, pos: &Position) {
+        *self = self.clone().with_position(pos);
+    }
+    fn evaluate(&mut self, pos: &Position) -> i32 {
+        self.score_for(pos.side_to_move())
+    }
+}
+
+/// Limits of the search. No limits means searching until [`MAX_PLY`] or the stop flag is set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+/// Result of the search, for the last completed iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// Best move, or `None` if there are no legal moves.
+    pub best_move: Option<Move>,
+    /// Score from the viewpoint of the side to move.
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+    /// Principal variation, starting with the best move.
+    pub pv: Vec<Move>,
+}
+
+/// Iterative-deepening principal variation search with a transposition table and quiescence search.
+pub struct Searcher<E: Evaluator> {
+    evaluator: E,
+    table: Arc<TranspositionTable>,
+    stop: Arc<AtomicBool>,
+    limits: Limits,
+    start: Instant,
+    nodes: u64,
+    aborted: bool,
+    pv: Vec<Vec<Move>>,
+}
+
+impl<E: Evaluator> Searcher<E> {
+    /// Creates a searcher with the transposition table of `hash_mb` megabytes.
+    pub fn new(evaluator: E, hash_mb: usize) -> Self {
+        Self::with_table(evaluator, Arc::new(TranspositionTable::new(hash_mb)))
+    }
+    /// Creates a searcher with the transposition table shared with other searchers.
+    pub fn with_table(evaluator: E, table: Arc<TranspositionTable>) -> Self {
+        Self {
+            evaluator,
+            table,
+            stop: Arc::new(AtomicBool::new(false)),
+            limits: Limits::default(),
+            start: Instant::now(),
+            nodes: 0,
+            aborted: false,
+            pv: vec![Vec::new(); MAX_PLY + 1],
+        }
+    }
+    /// Flag to stop the search from another thread. It's not cleared by the search.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+    pub fn table(&self) -> &Arc<TranspositionTable> {
+        &self.table
+    }
+    /// Replaces the transposition table with a new one of `hash_mb` megabytes, which is no longer shared.
+    pub fn resize_table(&mut self, hash_mb: usize) {
+        self.table = Arc::new(TranspositionTable::new(hash_mb));
+    }
+    /// Clears the transposition table, for a new game.
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+    pub fn evaluator_mut(&mut self) -> &mut E {
+        &mut self.evaluator
+    }
+    /// Searches the position within the limits. The position is restored after the search.
+    pub fn search(&mut self, pos: &mut Position, limits: &Limits) -> SearchResult {
+        self.search_with(pos, limits, |_| {})
+    }
+    /// Searches the position like [`Searcher::search`], calling `on_iteration` with the result of each completed iteration.
+    pub fn search_with<F>(
+        &mut self,
+        pos: &mut Position,
+        limits: &Limits,
+        mut on_iteration: F,
+    ) -> SearchResult
+    where
+        F: FnMut(&SearchResult),
+    {
+        self.limits = *limits;
+        self.start = Instant::now();
+        self.nodes = 0;
+        self.aborted = false;
+        self.table.new_search();
+        self.evaluator.init(pos);
+        let max_depth = limits
+            .depth
+            .unwrap_or(MAX_PLY as u32)
+            .clamp(1, MAX_PLY as u32 - 1);
+        let mut result = SearchResult {
+            best_move: pos.legal_moves().first().copied(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            pv: Vec::new(),
+        };
+        if result.best_move.is_none() {
+            result.score = -MATE;
+            return result;
+        }
+        for depth in 1..=max_depth {
+            let score = self.pvs(pos, depth as i32, -INFINITE, INFINITE, 0);
+            // The result of the aborted iteration is discarded, keeping the move, score and PV of the last one together.
+            if self.aborted {
+                break;
+            }
+            result = SearchResult {
+                best_move: self.pv[0].first().copied(),
+                score,
+                depth,
+                nodes: self.nodes,
+                pv: self.pv[0].clone(),
+            };
+            on_iteration(&result);
+            if is_mate_score(score) {
+                break;
+            }
+        }
+        result.nodes = self.nodes;
+        result
+    }
+    fn pvs(
+        &mut self,
+        pos: &mut Position,
+        depth: i32,
+        mut alpha: i32,
+        beta: i32,
+        ply: usize,
+    ) -> i32 {
+        self.pv[ply].clear();
+        let in_check = pos.in_check();
+        let depth = if in_check { depth + 1 } else { depth };
+        if depth <= 0 {
+            return self.quiescence(pos, alpha, beta, ply);
+        }
+        self.nodes += 1;
+        if self.is_limit_exceeded() {
+            return 0;
+        }
+        if ply > 0 {
+            // A repetition inside the search tree is scored at once, as it can be repeated until the fourth time.
+            match pos.repetition_status_since(ply) {
+                RepetitionStatus::Draw => return 0,
+                RepetitionStatus::Win => return MATE - ply as i32,
+                RepetitionStatus::Lose => return -(MATE - ply as i32),
+                _ => {}
+            }
+        }
+        if ply >= MAX_PLY {
+            return self.evaluator.evaluate(pos);
+        }
+        let key = pos.key();
+        let tt_move = match self.table.probe(key) {
+            Some(entry) => {
+                let score = score_from_tt(i32::from(entry.score), ply);
+                if ply > 0
+                    && beta - alpha == 1
+                    && i32::from(entry.depth) >= depth
+                    && match entry.bound {
+                        Bound::Exact => true,
+                        Bound::Lower => score >= beta,
+                        Bound::Upper => score <= alpha,
+                    }
+                {
+                    return score;
+                }
+                entry.best_move
+            }
+            None => None,
+        };
+        let mut moves = pos.legal_moves();
+        if moves.is_empty() {
+            return -(MATE - ply as i32);
+        }
+        order_moves(pos, &mut moves, tt_move);
+        let alpha_orig = alpha;
+        let (mut best_score, mut best_move) = (-INFINITE, None);
+        for (i, &m) in moves.iter().enumerate() {
+            pos.do_move_with(m, &mut self.evaluator);
+            let mut score;
+            if i == 0 {
+                score = -self.pvs(pos, depth - 1, -beta, -alpha, ply + 1);
+            } else {
+                score = -self.pvs(pos, depth - 1, -alpha - 1, -alpha, ply + 1);
+                if score > alpha && score < beta {
+                    score = -self.pvs(pos, depth - 1, -beta, -alpha, ply + 1);
+                }
+            }
+            pos.undo_move_with(m, &mut self.evaluator);
+            if self.aborted {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(m);
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(ply, m);
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > alpha_orig {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.table.store(
+            key,
+            best_move,
+            score_to_tt(best_score, ply) as i16,
+            depth.min(i32::from(u8::MAX)) as u8,
+            bound,
+        );
+        best_score
+    }
+    /// Searches captures and promotions until the position is quiet, or all evasions when in check.
+    fn quiescence(&mut self, pos: &mut Position, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        self.pv[ply].clear();
+        self.nodes += 1;
+        if self.is_limit_exceeded() {
+            return 0;
+        }
+        if ply >= MAX_PLY {
+            return self.evaluator.evaluate(pos);
+        }
+        let in_check = pos.in_check();
+        let mut best_score = -INFINITE;
+        let mut moves = if in_check {
+            pos.evasion_moves()
+        } else {
+            best_score = self.evaluator.evaluate(pos);
+            if best_score >= beta {
+                return best_score;
+            }
+            alpha = alpha.max(best_score);
+            pos.capture_moves()
+        };
+        if in_check && moves.is_empty() {
+            return -(MATE - ply as i32);
+        }
+        order_moves(pos, &mut moves, None);
+        for &m in &moves {
+            pos.do_move_with(m, &mut self.evaluator);
+            let score = -self.quiescence(pos, -beta, -alpha, ply + 1);
+            pos.undo_move_with(m, &mut self.evaluator);
+            if self.aborted {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(ply, m);
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        best_score
+    }
+    fn update_pv(&mut self, ply: usize, m: Move) {
+        let (parent, child) = self.pv.split_at_mut(ply + 1);
+        let pv = &mut parent[ply];
+        pv.clear();
+        pv.push(m);
+        pv.extend_from_slice(&child[0]);
+    }
+    fn is_limit_exceeded(&mut self) -> bool {
+        if matches!(self.limits.nodes, Some(limit) if self.nodes > limit)
+            || (self.nodes & 1023 == 0
+                && (self.stop.load(Ordering::Relaxed)
+                    || matches!(self.limits.time, Some(limit) if self.start.elapsed() > limit)))
+        {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+}
+
+/// Orders the moves: the move from the transposition table, captures by MVV-LVA, promotions, and then the others.
+fn order_moves(pos: &Position, moves: &mut [Move], tt_move: Option<Move>) {
+    let values = PieceValues::default();
+    moves.sort_by_cached_key(|&m| {
+        if Some(m) == tt_move {
+            return i32::MIN;
+        }
+        match m {
+            Move::Normal { from, to, promote } => {
+                let attacker = pos
+                    .piece_at(from)
+                    .map_or(PieceKind::Pawn, |p| p.piece_kind());
+                let victim = pos.piece_at(to).map_or(0, |p| values.value(p.piece_kind()));
+                let promotion = if promote {
+                    values.promotion_value(attacker)
+                } else {
+                    0
+                };
+                if victim > 0 {
+                    -(victim * 16 - values.value(attacker) / 16 + promotion)
+                } else {
+                    -promotion
+                }
+            }
+            Move::Drop { .. } => 0,
+        }
+    });
+}
+
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if is_mate_score(score) {
+        score + score.signum() * ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if is_mate_score(score) {
+        score - score.signum() * ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{PartialPosition, Piece, Square};
+    use shogi_usi_parser::FromUsi;
+
+    fn position(sfen: &str) -> Position {
+        Position::new(PartialPosition::from_usi(sfen).expect("failed to parse"))
+    }
+
+    fn assert_legal_pv(pos: &Position, pv: &[Move]) {
+        let mut pos = pos.clone();
+        for &m in pv {
+            assert!(pos.try_do_move(m).is_ok(), "illegal move in pv: {m:?}");
+        }
+    }
+
+    #[test]
+    fn mate() {
+        // P1 *  *  *  * -OU *  *  *  *
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  * +FU *  *  *  *
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9 *  *  *  * +OU *  *  *  *
+        // P+00KI
+        // P-00AL
+        // +
+        let mut pos = position("sfen 4k4/9/4P4/9/9/9/9/9/4K4 b G2r2b3g4s4n4l17p 1");
+        let key = pos.key();
+        let mut searcher = Searcher::new(MaterialPst::default(), 1);
+        let result = searcher.search(
+            &mut pos,
+            &Limits {
+                depth: Some(3),
+                ..Default::default()
+            },
+        );
+        let expected = Move::Drop {
+            to: Square::SQ_5B,
+            piece: Piece::B_G,
+        };
+        assert_eq!(Some(expected), result.best_move);
+        assert_eq!(vec![expected], result.pv);
+        assert_eq!(MATE - 1, result.score);
+        assert!(is_mate_score(result.score));
+        assert_eq!(key, pos.key());
+
+        // Checkmated
+        pos.do_move(expected);
+        let result = searcher.search(&mut pos, &Limits::default());
+        assert_eq!(None, result.best_move);
+        assert_eq!(-MATE, result.score);
+    }
+
+    #[test]
+    fn capture() {
+        // P1-KY-KE-GI-KI-OU-KI-GI-KE-KY
+        // P2 *  *  *  *  *  *  * -KA *
+        // P3-FU-FU-FU-FU-FU-FU-FU-FU-FU
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  * -HI *  *  *  *  *  *
+        // P6 *  * +FU *  *  *  *  *  *
+        // P7+FU+FU * +FU+FU+FU+FU+FU+FU
+        // P8 * +KA *  *  *  *  * +HI *
+        // P9+KY+KE+GI+KI+OU+KI+GI+KE+KY
+        // +
+        let mut pos =
+            position("sfen lnsgkgsnl/7b1/ppppppppp/9/2r6/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 1");
+        let mut searcher = Searcher::new(MaterialPst::default(), 1);
+        let mut iterations = Vec::new();
+        let result = searcher.search_with(
+            &mut pos,
+            &Limits {
+                depth: Some(4),
+                ..Default::default()
+            },
+            |result| iterations.push(result.depth),
+        );
+        assert_eq!(vec![1, 2, 3, 4], iterations);
+        assert_eq!(
+            Some(Move::Normal {
+                from: Square::SQ_7F,
+                to: Square::SQ_7E,
+                promote: false,
+            }),
+            result.best_move
+        );
+        assert_eq!(4, result.depth);
+        assert!(result.score > 0);
+        assert_legal_pv(&pos, &result.pv);
+        assert_eq!(Position::default().ply(), pos.ply());
+    }
+
+    #[test]
+    fn limits() {
+        let mut pos = Position::default();
+        let mut searcher = Searcher::new(MaterialPst::default(), 1);
+        let result = searcher.search(
+            &mut pos,
+            &Limits {
+                nodes: Some(5000),
+                ..Default::default()
+            },
+        );
+        assert!(result.nodes <= 5001);
+        assert!(result.best_move.is_some());
+        assert_legal_pv(&pos, &result.pv);
+
+        let result = searcher.search(
+            &mut pos,
+            &Limits {
+                time: Some(Duration::from_millis(50)),
+                ..Default::default()
+            },
+        );
+        assert!(result.best_move.is_some());
+        assert_legal_pv(&pos, &result.pv);
+
+        // Stopped before the search
+        searcher.stop_flag().store(true, Ordering::Relaxed);
+        let result = searcher.search(&mut pos, &Limits::default());
+        assert!(result.best_move.is_some());
+        assert_eq!(Position::default().to_sfen(), pos.to_sfen());
+    }
+}
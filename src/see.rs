@@ -0,0 +1,332 @@
+use crate::bitboard::Bitboard;
+use crate::tables::PROMOTABLE;
+use crate::Position;
+use shogi_core::{Color, Move, PieceKind, Square};
+
+/// Values of pieces on the board, used for static exchange evaluation.
+///
+/// Pieces in hand are valued the same as the unpromoted pieces on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceValues([i32; PieceKind::NUM]);
+
+impl PieceValues {
+    pub const fn new(values: [i32; PieceKind::NUM]) -> Self {
+        Self(values)
+    }
+    pub fn value(&self, pk: PieceKind) -> i32 {
+        self.0[pk.array_index()]
+    }
+    /// Value gained by capturing the piece: it's removed from the board and added to the hand as unpromoted.
+    pub fn capture_value(&self, pk: PieceKind) -> i32 {
+        self.value(pk) + self.value(pk.unpromote().unwrap_or(pk))
+    }
+    /// Value gained by promoting the piece.
+    pub fn promotion_value(&self, pk: PieceKind) -> i32 {
+        pk.promote()
+            .map_or(0, |promoted| self.value(promoted) - self.value(pk))
+    }
+}
+
+impl Default for PieceValues {
+    fn default() -> Self {
+        Self([
+            90,    // Pawn
+            315,   // Lance
+            405,   // Knight
+            495,   // Silver
+            540,   // Gold
+            855,   // Bishop
+            990,   // Rook
+            15000, // King
+            540,   // ProPawn
+            540,   // ProLance
+            540,   // ProKnight
+            540,   // ProSilver
+            945,   // ProBishop
+            1395,  // ProRook
+        ])
+    }
+}
+
+impl Position {
+    /// Static exchange evaluation of the move with the default piece values.
+    pub fn see(&self, m: Move) -> i32 {
+        self.see_with_values(m, &PieceValues::default())
+    }
+    /// Checks if the static exchange evaluation of the move with the default piece values is `threshold` or more.
+    pub fn see_ge(&self, m: Move, threshold: i32) -> bool {
+        self.see_ge_with_values(m, threshold, &PieceValues::default())
+    }
+    pub fn see_ge_with_values(&self, m: Move, threshold: i32, values: &PieceValues) -> bool {
+        self.see_with_values(m, values) >= threshold
+    }
+    /// Static exchange evaluation of the move: the material balance after the sequence of captures on the destination square,
+    /// where both players recapture with the least valuable piece, promoting if possible, or stop capturing when it's better.
+    ///
+    /// Pinned pieces are not considered, except for the king which never captures a defended piece.
+    pub fn see_with_values(&self, m: Move, values: &PieceValues) -> i32 {
+        let c = self.side_to_move();
+        let mut occ = self.occupied_bitboard();
+        let to = m.to();
+        // gains[i]: value gained by the i-th capture, supposing that the sequence stops after it
+        let mut gains = [0; 41];
+        let mut on_to = match m {
+            Move::Normal { from, to, promote } => {
+                let pk = self
+                    .piece_at(from)
+                    .expect("no piece at source")
+                    .piece_kind();
+                occ ^= Bitboard::single(from);
+                if let Some(p) = self.piece_at(to) {
+                    gains[0] += values.capture_value(p.piece_kind());
+                }
+                if promote {
+                    gains[0] += values.promotion_value(pk);
+                    pk.promote().expect("unpromotable piece")
+                } else {
+                    pk
+                }
+            }
+            Move::Drop { to, piece } => {
+                occ |= Bitboard::single(to);
+                piece.piece_kind()
+            }
+        };
+        let mut side = c.flip();
+        let mut depth = 1;
+        loop {
+            let attackers = self.attackers_to_bitboard(side, to, &occ) & occ;
+            let Some((from, pk)) = attackers
+                .into_iter()
+                .map(|sq| (sq, self.piece_at(sq).expect("no piece").piece_kind()))
+                .min_by_key(|&(_, pk)| values.value(pk))
+            else {
+                break;
+            };
+            occ ^= Bitboard::single(from);
+            if pk == PieceKind::King
+                && !(self.attackers_to_bitboard(side.flip(), to, &occ) & occ).is_empty()
+            {
+                break;
+            }
+            gains[depth] = values.capture_value(on_to) - gains[depth - 1];
+            on_to = if can_promote(pk, from, to, side) {
+                gains[depth] += values.promotion_value(pk);
+                pk.promote().expect("unpromotable piece")
+            } else {
+                pk
+            };
+            side = side.flip();
+            depth += 1;
+        }
+        for d in (1..depth).rev() {
+            gains[d - 1] = -(-gains[d - 1]).max(gains[d]);
+        }
+        gains[0]
+    }
+}
+
+fn can_promote(pk: PieceKind, from: Square, to: Square, c: Color) -> bool {
+    pk.promote().is_some()
+        && (PROMOTABLE[from.array_index()][c.array_index()]
+            || PROMOTABLE[to.array_index()][c.array_index()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::consts::square::*;
+    use shogi_core::{PartialPosition, Piece};
+    use shogi_usi_parser::FromUsi;
+
+    fn normal(from: Square, to: Square, promote: bool) -> Move {
+        Move::Normal { from, to, promote }
+    }
+
+    #[test]
+    fn see() {
+        let test_cases = [
+            // P1 *  *  *  * -OU *  *  *  *
+            // P2 *  *  *  *  *  *  *  *  *
+            // P3 *  *  *  *  *  *  *  *  *
+            // P4 *  *  *  * -FU *  *  *  *
+            // P5 *  *  *  * +FU *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  * +OU *  *  *  *
+            // +
+            (
+                "sfen 4k4/9/9/4p4/4P4/9/9/9/4K4 b - 1",
+                normal(SQ_5E, SQ_5D, false),
+                180,
+            ),
+            // P1 *  *  *  * -OU *  *  *  *
+            // P2 *  *  *  *  *  *  *  *  *
+            // P3 *  *  *  * -KI *  *  *  *
+            // P4 *  *  *  * -FU *  *  *  *
+            // P5 *  *  *  * +FU *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  * +OU *  *  *  *
+            // +
+            (
+                "sfen 4k4/9/4g4/4p4/4P4/9/9/9/4K4 b - 1",
+                normal(SQ_5E, SQ_5D, false),
+                0,
+            ),
+            // P1 *  *  *  * -OU *  *  *  *
+            // P2 *  *  *  *  *  *  *  *  *
+            // P3 *  *  *  * -KI *  *  *  *
+            // P4 *  *  *  * -FU *  *  *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  * +HI *  *  *  *
+            // P9 *  *  *  * +OU *  *  *  *
+            // +
+            (
+                "sfen 4k4/9/4g4/4p4/9/9/9/4R4/4K4 b - 1",
+                normal(SQ_5H, SQ_5D, false),
+                -1800,
+            ),
+            // x-ray
+            // P1 *  *  *  * -OU *  *  *  *
+            // P2 *  *  *  *  *  *  *  *  *
+            // P3 *  *  *  * -KI *  *  *  *
+            // P4 *  *  *  * -FU *  *  *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  * +HI *  *  *  *
+            // P8 *  *  *  * +HI *  *  *  *
+            // P9+OU *  *  *  *  *  *  *  *
+            // +
+            (
+                "sfen 4k4/9/4g4/4p4/9/9/4R4/4R4/K8 b - 1",
+                normal(SQ_5G, SQ_5D, false),
+                -720,
+            ),
+            // promotion
+            // P1 *  *  *  * -OU *  *  *  *
+            // P2 *  *  *  *  *  *  *  *  *
+            // P3 *  *  *  * -FU *  *  *  *
+            // P4 *  *  * +GI *  *  *  *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  * +OU *  *  *  *
+            // +
+            (
+                "sfen 4k4/9/4p4/5S3/9/9/9/9/4K4 b - 1",
+                normal(SQ_4D, SQ_5C, true),
+                225,
+            ),
+            (
+                "sfen 4k4/9/4p4/5S3/9/9/9/9/4K4 b - 1",
+                normal(SQ_4D, SQ_5C, false),
+                180,
+            ),
+            // recapture with promotion
+            // P1 *  *  *  * -OU *  *  *  *
+            // P2 *  *  *  *  *  *  *  *  *
+            // P3 *  *  *  *  *  *  *  *  *
+            // P4 *  *  *  *  *  *  *  *  *
+            // P5 *  *  *  *  *  * -KA *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  * -FU *  *  *  *
+            // P8 *  *  *  * +KI *  *  *  *
+            // P9+OU *  *  *  *  *  *  *  *
+            // +
+            (
+                "sfen 4k4/9/9/9/6b2/9/4p4/4G4/K8 b - 1",
+                normal(SQ_5H, SQ_5G, false),
+                180 - 1080 - 90,
+            ),
+            // drop
+            // P1 *  *  *  * -OU *  *  *  *
+            // P2 *  *  *  *  *  *  *  *  *
+            // P3 *  *  *  * -KI *  *  *  *
+            // P4 *  *  *  *  *  *  *  *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  * +OU *  *  *  *
+            // P+00HI
+            // +
+            (
+                "sfen 4k4/9/4g4/9/9/9/9/9/4K4 b R 1",
+                Move::Drop {
+                    to: SQ_5D,
+                    piece: Piece::B_R,
+                },
+                -1980,
+            ),
+            (
+                "sfen 4k4/9/4g4/9/9/9/9/9/4K4 b R 1",
+                Move::Drop {
+                    to: SQ_5E,
+                    piece: Piece::B_R,
+                },
+                0,
+            ),
+            // king never captures a defended piece
+            // P1 *  *  * -OU *  *  *  *  *
+            // P2 *  *  *  * -FU *  *  *  *
+            // P3 *  *  *  * +FU *  *  *  *
+            // P4 *  *  *  * +KY *  *  *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9+OU *  *  *  *  *  *  *  *
+            // +
+            (
+                "sfen 3k5/4p4/4P4/4L4/9/9/9/9/K8 b - 1",
+                normal(SQ_5C, SQ_5B, false),
+                180,
+            ),
+            (
+                "sfen 3k5/4p4/4P4/4L4/9/9/9/9/K8 b - 1",
+                normal(SQ_5C, SQ_5B, true),
+                630,
+            ),
+            (
+                "sfen 3k5/4p4/4P4/9/9/9/9/9/K8 b - 1",
+                normal(SQ_5C, SQ_5B, true),
+                0,
+            ),
+        ];
+        for (sfen, m, expected) in test_cases {
+            let pos = Position::new(PartialPosition::from_usi(sfen).expect("failed to parse"));
+            assert_eq!(expected, pos.see(m), "{sfen} {m:?}");
+            assert!(pos.see_ge(m, expected));
+            assert!(!pos.see_ge(m, expected + 1));
+        }
+    }
+
+    #[test]
+    fn see_with_values() {
+        // P1 *  *  *  * -OU *  *  *  *
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  * -KI *  *  *  *
+        // P4 *  *  *  * -FU *  *  *  *
+        // P5 *  *  *  * +FU *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9 *  *  *  * +OU *  *  *  *
+        // +
+        let pos = Position::new(
+            PartialPosition::from_usi("sfen 4k4/9/4g4/4p4/4P4/9/9/9/4K4 b - 1")
+                .expect("failed to parse"),
+        );
+        let values = PieceValues::new([1, 3, 4, 5, 6, 8, 10, 1000, 6, 6, 6, 6, 10, 12]);
+        let m = normal(SQ_5E, SQ_5D, false);
+        assert_eq!(0, pos.see_with_values(m, &values));
+        assert!(pos.see_ge_with_values(m, 0, &values));
+        assert!(!pos.see_ge_with_values(m, 1, &values));
+    }
+}
@@ -194,32 +194,60 @@ pub static ATTACK_TABLE: Lazy<AttackTable> = Lazy::new(|| AttackTable {
     ou: PieceAttackTable::new(&[PieceAttackTable::BOU_DELTAS, PieceAttackTable::WOU_DELTAS]),
 });
 
+/// Direction from `sq0` to `sq1`, if they are aligned in one of 8 directions.
+fn direction(sq0: Square, sq1: Square) -> Option<Delta> {
+    let (df, dr) = (
+        sq1.file() as i8 - sq0.file() as i8,
+        sq1.rank() as i8 - sq0.rank() as i8,
+    );
+    if (df | dr == 0) || (df != 0 && dr != 0 && df.abs() != dr.abs()) {
+        return None;
+    }
+    #[rustfmt::skip]
+    let delta = match (df.cmp(&0), dr.cmp(&0)) {
+        (Ordering::Equal,   Ordering::Less)    => Delta::N,
+        (Ordering::Less,    Ordering::Equal)   => Delta::E,
+        (Ordering::Equal,   Ordering::Greater) => Delta::S,
+        (Ordering::Greater, Ordering::Equal)   => Delta::W,
+        (Ordering::Less,    Ordering::Less)    => Delta::NE,
+        (Ordering::Less,    Ordering::Greater) => Delta::SE,
+        (Ordering::Greater, Ordering::Greater) => Delta::SW,
+        (Ordering::Greater, Ordering::Less)    => Delta::NW,
+        _ => unreachable!(),
+    };
+    Some(delta)
+}
+
 /// A table of the squares between two squares. "Between" is defined only for 8 directions.
 pub(crate) static BETWEEN_TABLE: Lazy<[[Bitboard; Square::NUM]; Square::NUM]> = Lazy::new(|| {
     let mut bbs = [[Bitboard::empty(); Square::NUM]; Square::NUM];
     for sq0 in Square::all() {
         for sq1 in Square::all() {
-            let (df, dr) = (
-                sq1.file() as i8 - sq0.file() as i8,
-                sq1.rank() as i8 - sq0.rank() as i8,
-            );
-            if (df | dr == 0) || (df != 0 && dr != 0 && df.abs() != dr.abs()) {
-                continue;
+            if let Some(delta) = direction(sq0, sq1) {
+                bbs[sq0.array_index()][sq1.array_index()] =
+                    sliding_attack(sq0, Bitboard::single(sq1), delta) & !Bitboard::single(sq1);
+            }
+        }
+    }
+    bbs
+});
+
+/// A table of the whole lines from edge to edge through two squares, including both of them.
+/// "Line" is defined only for 8 directions, as well as "between".
+pub(crate) static LINE_TABLE: Lazy<[[Bitboard; Square::NUM]; Square::NUM]> = Lazy::new(|| {
+    let mut bbs = [[Bitboard::empty(); Square::NUM]; Square::NUM];
+    for sq0 in Square::all() {
+        for sq1 in Square::all() {
+            if let Some(delta) = direction(sq0, sq1) {
+                let opposite = Delta {
+                    file: -delta.file,
+                    rank: -delta.rank,
+                };
+                bbs[sq0.array_index()][sq1.array_index()] =
+                    sliding_attack(sq0, Bitboard::empty(), delta)
+                        | sliding_attack(sq0, Bitboard::empty(), opposite)
+                        | Bitboard::single(sq0);
             }
-            #[rustfmt::skip]
-            let delta = match (df.cmp(&0), dr.cmp(&0)) {
-                (Ordering::Equal,   Ordering::Less)    => Delta::N,
-                (Ordering::Less,    Ordering::Equal)   => Delta::E,
-                (Ordering::Equal,   Ordering::Greater) => Delta::S,
-                (Ordering::Greater, Ordering::Equal)   => Delta::W,
-                (Ordering::Less,    Ordering::Less)    => Delta::NE,
-                (Ordering::Less,    Ordering::Greater) => Delta::SE,
-                (Ordering::Greater, Ordering::Greater) => Delta::SW,
-                (Ordering::Greater, Ordering::Less)    => Delta::NW,
-                _ => unreachable!(),
-            };
-            bbs[sq0.array_index()][sq1.array_index()] =
-                sliding_attack(sq0, Bitboard::single(sq1), delta) & !Bitboard::single(sq1);
         }
     }
     bbs
@@ -247,6 +275,19 @@ pub(crate) static PROMOTABLE: Lazy<[[bool; Color::NUM]; Square::NUM]> = Lazy::ne
     table
 });
 
+/// Promotion zones (敵陣) of each color.
+pub(crate) static PROMOTION_ZONES: Lazy<[Bitboard; Color::NUM]> = Lazy::new(|| {
+    let mut bbs = [Bitboard::empty(); Color::NUM];
+    for sq in Square::all() {
+        for c in Color::all() {
+            if PROMOTABLE[sq.array_index()][c.array_index()] {
+                bbs[c.array_index()] |= Bitboard::single(sq);
+            }
+        }
+    }
+    bbs
+});
+
 pub(crate) static FILES: Lazy<[Bitboard; 10]> = Lazy::new(|| {
     let mut bbs = [Bitboard::empty(); 10];
     for sq in Square::all() {
@@ -255,6 +296,14 @@ pub(crate) static FILES: Lazy<[Bitboard; 10]> = Lazy::new(|| {
     bbs
 });
 
+pub(crate) static RANKS: Lazy<[Bitboard; 10]> = Lazy::new(|| {
+    let mut bbs = [Bitboard::empty(); 10];
+    for sq in Square::all() {
+        bbs[usize::from(sq.rank())] |= Bitboard::single(sq);
+    }
+    bbs
+});
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,533 @@
+//! Df-pn (depth-first proof-number search) solver for tsume shogi (詰将棋).
+
+use crate::Position;
+use shogi_core::Move;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const INFINITE: u32 = u32::MAX / 2;
+/// Nodes to search for each unsolved position, to find shorter mates outside the proof tree.
+const PV_SEARCH_NODES: u64 = 100;
+const MAX_MATE_PLIES: u32 = 1024;
+
+/// Result of solving a tsume problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TsumeResult {
+    /// Checkmate found, with the principal variation.
+    Mate(Vec<Move>),
+    /// Proven that there is no checkmate.
+    NoMate,
+    /// Search aborted by the node or time limit, before the principal variation is determined.
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    pn: u32,
+    dn: u32,
+}
+
+impl Entry {
+    const UNKNOWN: Entry = Entry { pn: 1, dn: 1 };
+    const PROVEN: Entry = Entry {
+        pn: 0,
+        dn: INFINITE,
+    };
+    const DISPROVEN: Entry = Entry {
+        pn: INFINITE,
+        dn: 0,
+    };
+}
+
+#[derive(Default)]
+struct Memo {
+    /// Exact mate lengths.
+    lengths: HashMap<u64, u32>,
+    /// Largest bounds within which no mate was found.
+    failed: HashMap<u64, u32>,
+    /// Positions already searched for the mate length.
+    searched: HashSet<u64>,
+}
+
+/// Df-pn solver for tsume shogi, searching for a checkmate by consecutive checks of the side to move.
+///
+/// Pawn-drop mates (打ち歩詰め) are never accepted, and repetitions are treated as failures of the attacker.
+pub struct Solver {
+    node_limit: Option<u64>,
+    time_limit: Option<Duration>,
+    stop: Arc<AtomicBool>,
+    table: HashMap<u64, Entry>,
+    path: HashSet<u64>,
+    nodes: u64,
+    local_node_limit: Option<u64>,
+    start: Instant,
+    aborted: bool,
+}
+
+impl Solver {
+    /// Creates a solver with the node and time limits for each [`Solver::solve`].
+    pub fn new(node_limit: Option<u64>, time_limit: Option<Duration>) -> Self {
+        Self {
+            node_limit,
+            time_limit,
+            stop: Arc::new(AtomicBool::new(false)),
+            table: HashMap::new(),
+            path: HashSet::new(),
+            nodes: 0,
+            local_node_limit: None,
+            start: Instant::now(),
+            aborted: false,
+        }
+    }
+    /// Flag to stop solving from another thread. It's not cleared by the solver.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+    /// Number of nodes searched by the last [`Solver::solve`].
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+    /// Solves the position with the side to move as the attacker.
+    ///
+    /// The principal variation is the shortest mate among the proven moves of the attacker,
+    /// against the longest resistance of the defender.
+    pub fn solve(&mut self, pos: &mut Position) -> TsumeResult {
+        self.table.clear();
+        self.path.clear();
+        self.nodes = 0;
+        self.start = Instant::now();
+        self.aborted = false;
+        let entry = self.mid(pos, INFINITE - 1, INFINITE - 1, true);
+        if entry.pn == 0 {
+            let mut memo = Memo::default();
+            // Iterative deepening finds the shortest mate first.
+            let found = (1..MAX_MATE_PLIES).step_by(2).any(|bound| {
+                self.aborted || self.mate_length(pos, true, bound, &mut memo).is_some()
+            });
+            if !found || self.aborted {
+                return TsumeResult::Unknown;
+            }
+            let mut pv = Vec::new();
+            self.principal_variation(pos, true, &memo.lengths, &mut pv);
+            TsumeResult::Mate(pv)
+        } else if entry.dn == 0 && !self.aborted {
+            TsumeResult::NoMate
+        } else {
+            TsumeResult::Unknown
+        }
+    }
+    fn mid(&mut self, pos: &mut Position, thpn: u32, thdn: u32, or_node: bool) -> Entry {
+        let key = pos.key();
+        let moves = if or_node {
+            pos.check_moves()
+        } else {
+            pos.legal_moves()
+        };
+        if moves.is_empty() {
+            let entry = if or_node {
+                Entry::DISPROVEN
+            } else {
+                Entry::PROVEN
+            };
+            self.table.insert(key, entry);
+            return entry;
+        }
+        self.path.insert(key);
+        let mut entry = Entry::UNKNOWN;
+        loop {
+            self.nodes += 1;
+            if self.is_limit_exceeded() {
+                break;
+            }
+            // (index, value to minimize, second best value, pn, dn) of the best child
+            let (mut best, mut best_value, mut second) = (0, INFINITE, INFINITE);
+            let (mut best_pn, mut best_dn) = (INFINITE, INFINITE);
+            let (mut min_value, mut sum_value) = (INFINITE, 0_u32);
+            for (i, &m) in moves.iter().enumerate() {
+                let child = self.child_entry(pos, m);
+                // OR node: minimize pn, sum up dn. AND node: minimize dn, sum up pn.
+                let (value, other) = if or_node {
+                    (child.pn, child.dn)
+                } else {
+                    (child.dn, child.pn)
+                };
+                min_value = min_value.min(value);
+                sum_value = sum_value.saturating_add(other).min(INFINITE);
+                if value < best_value {
+                    second = best_value;
+                    best = i;
+                    best_value = value;
+                    best_pn = child.pn;
+                    best_dn = child.dn;
+                } else if value < second {
+                    second = value;
+                }
+            }
+            entry = if or_node {
+                Entry {
+                    pn: min_value,
+                    dn: sum_value,
+                }
+            } else {
+                Entry {
+                    pn: sum_value,
+                    dn: min_value,
+                }
+            };
+            self.table.insert(key, entry);
+            if entry.pn >= thpn || entry.dn >= thdn {
+                break;
+            }
+            let (child_thpn, child_thdn) = if or_node {
+                (
+                    thpn.min(second.saturating_add(1)),
+                    (thdn - entry.dn).saturating_add(best_dn).min(INFINITE - 1),
+                )
+            } else {
+                (
+                    (thpn - entry.pn).saturating_add(best_pn).min(INFINITE - 1),
+                    thdn.min(second.saturating_add(1)),
+                )
+            };
+            let m = moves[best];
+            pos.do_move(m);
+            self.mid(pos, child_thpn, child_thdn, !or_node);
+            pos.undo_move(m);
+            if self.aborted {
+                break;
+            }
+        }
+        self.path.remove(&key);
+        entry
+    }
+    fn child_entry(&self, pos: &mut Position, m: Move) -> Entry {
+        pos.do_move(m);
+        let key = pos.key();
+        let entry = if self.path.contains(&key) {
+            // 連続王手の千日手は攻め方の負け
+            Entry::DISPROVEN
+        } else {
+            self.table.get(&key).copied().unwrap_or(Entry::UNKNOWN)
+        };
+        pos.undo_move(m);
+        entry
+    }
+    fn is_limit_exceeded(&mut self) -> bool {
+        self.is_aborted() || matches!(self.local_node_limit, Some(limit) if self.nodes > limit)
+    }
+    /// Checks the node and time limits and the stop flag, which abort the whole solving.
+    fn is_aborted(&mut self) -> bool {
+        if matches!(self.node_limit, Some(limit) if self.nodes > limit)
+            || (self.nodes & 1023 == 0
+                && (self.stop.load(Ordering::Relaxed)
+                    || matches!(self.time_limit, Some(limit) if self.start.elapsed() > limit)))
+        {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+    /// Calculates the length of the mate within `bound` plies: the shortest for the attacker,
+    /// the longest for the defender. Only the proven positions are searched, including those
+    /// proven here with a small node budget, so that the search is bounded by the proof tree.
+    fn mate_length(
+        &mut self,
+        pos: &mut Position,
+        or_node: bool,
+        bound: u32,
+        memo: &mut Memo,
+    ) -> Option<u32> {
+        self.nodes += 1;
+        if self.is_aborted() {
+            return None;
+        }
+        let key = pos.key();
+        if let Some(&len) = memo.lengths.get(&key) {
+            return Some(len).filter(|&len| len <= bound);
+        }
+        if self.path.contains(&key) || matches!(memo.failed.get(&key), Some(&b) if b >= bound) {
+            return None;
+        }
+        if !matches!(self.table.get(&key), Some(e) if e.pn == 0 || e.dn == 0)
+            && memo.searched.insert(key)
+        {
+            self.local_node_limit = Some(self.nodes + PV_SEARCH_NODES);
+            self.mid(pos, INFINITE - 1, INFINITE - 1, or_node);
+            self.local_node_limit = None;
+        }
+        if !matches!(self.table.get(&key), Some(e) if e.pn == 0) {
+            return None;
+        }
+        let moves = if or_node {
+            pos.check_moves()
+        } else {
+            pos.legal_moves()
+        };
+        if moves.is_empty() {
+            if or_node {
+                memo.failed.insert(key, u32::MAX);
+                return None;
+            }
+            memo.lengths.insert(key, 0);
+            return Some(0);
+        }
+        if bound == 0 {
+            memo.failed.insert(key, bound);
+            return None;
+        }
+        self.path.insert(key);
+        let mut ret = if or_node { None } else { Some(0) };
+        for m in moves {
+            // Only shorter mates than the best so far are interesting for the attacker.
+            let child_bound = match ret {
+                Some(len) if or_node => len - 1,
+                _ => bound - 1,
+            };
+            pos.do_move(m);
+            let len = self.mate_length(pos, !or_node, child_bound, memo);
+            pos.undo_move(m);
+            if self.aborted {
+                self.path.remove(&key);
+                return None;
+            }
+            match (or_node, len) {
+                (true, Some(len)) => ret = Some(len + 1),
+                (false, Some(len)) => ret = ret.map(|r: u32| r.max(len + 1)),
+                (false, None) => {
+                    ret = None;
+                    break;
+                }
+                (true, None) => {}
+            }
+            if or_node && ret == Some(1) {
+                break;
+            }
+        }
+        self.path.remove(&key);
+        match ret {
+            Some(len) => {
+                memo.lengths.insert(key, len);
+            }
+            None => {
+                memo.failed.insert(key, bound);
+            }
+        }
+        ret
+    }
+    fn principal_variation(
+        &self,
+        pos: &mut Position,
+        or_node: bool,
+        lengths: &HashMap<u64, u32>,
+        pv: &mut Vec<Move>,
+    ) {
+        let len = match lengths.get(&pos.key()) {
+            Some(&len) if len > 0 => len,
+            _ => return,
+        };
+        let moves = if or_node {
+            pos.check_moves()
+        } else {
+            pos.legal_moves()
+        };
+        for m in moves {
+            pos.do_move(m);
+            let found = lengths.get(&pos.key()) == Some(&(len - 1));
+            if found {
+                pv.push(m);
+                self.principal_variation(pos, !or_node, lengths, pv);
+            }
+            pos.undo_move(m);
+            if found {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{PartialPosition, ToUsi};
+    use shogi_usi_parser::FromUsi;
+
+    fn solve(sfen: &str, node_limit: Option<u64>) -> TsumeResult {
+        let mut pos = Position::new(PartialPosition::from_usi(sfen).expect("failed to parse"));
+        Solver::new(node_limit, None).solve(&mut pos)
+    }
+
+    fn pv_usi(result: &TsumeResult) -> Vec<String> {
+        match result {
+            TsumeResult::Mate(pv) => pv.iter().map(|m| m.to_usi_owned()).collect(),
+            _ => panic!("no mate: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn mate() {
+        let test_cases = [
+            // 頭金
+            // P1 *  *  *  * -OU *  *  *  *
+            // P2 *  *  *  *  *  *  *  *  *
+            // P3 *  *  *  * +FU *  *  *  *
+            // P4 *  *  *  *  *  *  *  *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  * +OU *  *  *  *
+            // P+00KI
+            // P-00AL
+            // +
+            (
+                "sfen 4k4/9/4P4/9/9/9/9/9/4K4 b G2r2b3g4s4n4l17p 1",
+                vec!["G*5b"],
+            ),
+            // P1 *  *  *  *  *  *  *  *  *
+            // P2 *  *  *  * +KI * -OU *  *
+            // P3 *  *  *  *  *  *  *  *  *
+            // P4 *  *  *  *  *  *  *  *  *
+            // P5 *  *  *  *  * +RY *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  *  *  *  *  *  *
+            // P+00KI00GI
+            // +
+            (
+                "sfen 9/5G1k1/9/9/6+R2/9/9/9/9 b GS 1",
+                vec!["3e2d", "2b1a", "G*2b"],
+            ),
+            // P1 *  *  *  *  *  *  * -OU *
+            // P2 *  *  *  *  *  *  *  *  *
+            // P3 *  *  *  * +RY *  *  *  *
+            // P4 *  *  *  *  *  * +KY *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  *  *  *  *  *  *
+            // P+00GI00GI
+            // +
+            (
+                "sfen 7k1/9/5+R3/6L2/9/9/9/9/9 b 2S 1",
+                vec!["4c2c", "2a1a", "S*1b"],
+            ),
+        ];
+        for (sfen, expected) in test_cases {
+            assert_eq!(expected, pv_usi(&solve(sfen, None)), "{sfen}");
+        }
+    }
+
+    #[test]
+    fn longest_resistance() {
+        // P1 *  *  *  *  *  *  *  *  *
+        // P2 *  *  *  *  *  *  * -OU *
+        // P3 *  *  *  *  *  *  *  *  *
+        // P4 *  *  *  *  *  * -FU *  *
+        // P5 *  *  *  *  *  *  * -FU *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9 *  *  *  *  *  *  *  *  *
+        // P+00HI00HI
+        // +
+        let sfen = "sfen 9/7k1/9/6p2/7p1/9/9/9/9 b 2R 1";
+        let result = solve(sfen, None);
+        let pv = match &result {
+            TsumeResult::Mate(pv) => pv,
+            _ => panic!("no mate: {result:?}"),
+        };
+        assert_eq!(25, pv.len());
+        let mut pos = Position::new(PartialPosition::from_usi(sfen).expect("failed to parse"));
+        for (i, &m) in pv.iter().enumerate() {
+            assert_eq!(i % 2 == 0, pos.check_moves().contains(&m));
+            assert!(pos.legal_moves().contains(&m));
+            pos.do_move(m);
+        }
+        assert!(pos.in_check());
+        assert!(pos.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn no_mate() {
+        let test_cases = [
+            // 打ち歩詰め
+            // P1 *  *  *  *  *  *  *  *  *
+            // P2 *  *  *  *  *  *  * -FU-FU
+            // P3 *  *  *  *  *  *  *  * -OU
+            // P4 *  *  *  *  *  *  * +FU *
+            // P5 *  *  *  *  *  *  * +KI *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  *  *
+            // P9 *  *  *  *  *  *  *  *  *
+            // P+00FU
+            // P-00AL
+            // +
+            "sfen 9/7pp/8k/7P1/7G1/9/9/9/9 b P2r2b3g4s4n4l14p 1",
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+        ];
+        for sfen in test_cases {
+            assert_eq!(TsumeResult::NoMate, solve(sfen, None), "{sfen}");
+        }
+    }
+
+    #[test]
+    fn node_limit() {
+        let mut pos = Position::new(
+            PartialPosition::from_usi("sfen 9/7k1/9/6p2/7p1/9/9/9/9 b 2R 1")
+                .expect("failed to parse"),
+        );
+        let mut solver = Solver::new(Some(100), None);
+        assert_eq!(TsumeResult::Unknown, solver.solve(&mut pos));
+        assert!(solver.nodes() > 100);
+
+        let mut solver = Solver::new(None, None);
+        solver.stop_flag().store(true, Ordering::Relaxed);
+        assert_eq!(TsumeResult::Unknown, solver.solve(&mut pos));
+    }
+
+    #[test]
+    fn shortest_mate() {
+        // P1 *  *  *  *  *  *  * -OU *
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  *  *  *  *  *  *
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9 *  *  *  *  *  *  *  *  *
+        // P+00HI00HI00KA00KA
+        // +
+        let sfen = "sfen 7k1/9/9/9/9/9/9/9/9 b 2R2B 1";
+        let result = solve(sfen, None);
+        let pv = match &result {
+            TsumeResult::Mate(pv) => pv,
+            _ => panic!("no mate: {result:?}"),
+        };
+        // No mate in 5, and the defender can resist until the 7th ply
+        assert_eq!(7, pv.len());
+        let mut pos = Position::new(PartialPosition::from_usi(sfen).expect("failed to parse"));
+        for &m in pv {
+            assert!(pos.legal_moves().contains(&m));
+            pos.do_move(m);
+        }
+        assert!(pos.in_check());
+        assert!(pos.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn node_limit_after_proof() {
+        // The root is proven within the limit, but the shortest mate isn't determined.
+        let mut pos = Position::new(
+            PartialPosition::from_usi("sfen 7k1/9/9/9/9/9/9/9/9 b 2R2B 1")
+                .expect("failed to parse"),
+        );
+        let mut solver = Solver::new(Some(20_000), None);
+        assert_eq!(TsumeResult::Unknown, solver.solve(&mut pos));
+        assert_eq!(20_001, solver.nodes());
+    }
+}
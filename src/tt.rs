@@ -0,0 +1,361 @@
+//! Transposition table shared by search threads, keyed by [`Position::key`](crate::Position::key).
+
+use shogi_core::{Color, Move, Piece, PieceKind, Square};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+const BUCKET_SIZE: usize = 4;
+const GENERATION_MASK: u8 = 0x3f;
+
+/// Bound of the score stored in the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The exact score
+    Exact,
+    /// The score is a lower bound: the search failed high
+    Lower,
+    /// The score is an upper bound: the search failed low
+    Upper,
+}
+
+/// Entry found in the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub best_move: Option<Move>,
+    pub score: i16,
+    pub depth: u8,
+    pub bound: Bound,
+    /// Generation of the search which stored the entry, in 6 bits.
+    pub generation: u8,
+}
+
+impl Entry {
+    // Layout of 64 bits: move (16) | score (16) | depth (8) | bound (2) | generation (6)
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+        u64::from(self.best_move.map_or(0, encode_move))
+            | u64::from(self.score as u16) << 16
+            | u64::from(self.depth) << 32
+            | bound << 40
+            | u64::from(self.generation & GENERATION_MASK) << 42
+    }
+    fn unpack(data: u64) -> Option<Self> {
+        let bound = match (data >> 40) & 3 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        Some(Self {
+            best_move: decode_move(data as u16),
+            score: (data >> 16) as u16 as i16,
+            depth: (data >> 32) as u8,
+            bound,
+            generation: (data >> 42) as u8 & GENERATION_MASK,
+        })
+    }
+}
+
+/// Encodes the move in 16 bits, in the same representation as `shogi_core::CompactMove`.
+fn encode_move(m: Move) -> u16 {
+    match m {
+        Move::Normal { from, to, promote } => {
+            u16::from(promote) << 15 | u16::from(from.index()) << 8 | u16::from(to.index())
+        }
+        Move::Drop { piece, to } => u16::from(piece.as_u8()) << 8 | 128 | u16::from(to.index()),
+    }
+}
+
+fn decode_move(value: u16) -> Option<Move> {
+    let to = Square::from_u8(value as u8 & 127)?;
+    if value & 128 != 0 {
+        let piece = (value >> 8) as u8;
+        let color = if piece & 16 != 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let piece = Piece::new(PieceKind::from_u8(piece & 15)?, color);
+        Some(Move::Drop { piece, to })
+    } else {
+        let from = Square::from_u8((value >> 8) as u8 & 127)?;
+        Some(Move::Normal {
+            from,
+            to,
+            promote: value & 32768 != 0,
+        })
+    }
+}
+
+/// A slot holding `key ^ data` and `data`, so that a slot torn by concurrent stores fails the key verification.
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    fn load(&self) -> (u64, u64) {
+        let data = self.data.load(Ordering::Relaxed);
+        (self.key.load(Ordering::Relaxed) ^ data, data)
+    }
+    fn store(&self, key: u64, data: u64) {
+        self.key.store(key ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+#[repr(align(64))]
+struct Bucket([Slot; BUCKET_SIZE]);
+
+/// Lock-free transposition table with buckets of 4 entries, shared by search threads.
+///
+/// Each entry is verified by the full 64-bit key, and a store racing with other stores or probes is
+/// never seen as a valid entry with mixed data. Entries of shallower depth and older generations are replaced first.
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    generation: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// Creates a table of `mb` megabytes, which has at least one bucket.
+    pub fn new(mb: usize) -> Self {
+        let mut table = Self {
+            buckets: Vec::new(),
+            generation: AtomicU8::new(0),
+        };
+        table.resize(mb);
+        table
+    }
+    /// Resizes the table to `mb` megabytes. All the entries are cleared.
+    pub fn resize(&mut self, mb: usize) {
+        let len = ((mb << 20) / std::mem::size_of::<Bucket>()).max(1);
+        self.buckets = Vec::new();
+        self.buckets.resize_with(len, Bucket::default);
+    }
+    /// Number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+    /// Clears all the entries.
+    pub fn clear(&self) {
+        for slot in self.buckets.iter().flat_map(|bucket| &bucket.0) {
+            slot.store(0, 0);
+        }
+        self.generation.store(0, Ordering::Relaxed);
+    }
+    /// Advances the generation for a new search, so that the entries of the previous searches are replaced first.
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+    fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed) & GENERATION_MASK
+    }
+    /// Finds the entry for the key.
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.bucket(key)
+            .0
+            .iter()
+            .find_map(|slot| match slot.load() {
+                (k, data) if k == key => Entry::unpack(data),
+                _ => None,
+            })
+    }
+    /// Stores the entry for the key, with the current generation.
+    /// The best move already stored for the key is kept if `best_move` is `None`.
+    pub fn store(&self, key: u64, best_move: Option<Move>, score: i16, depth: u8, bound: Bound) {
+        let generation = self.generation();
+        let bucket = self.bucket(key);
+        let mut replace = &bucket.0[0];
+        let mut replace_value = i32::MAX;
+        let mut best_move = best_move;
+        for slot in &bucket.0 {
+            let (k, data) = slot.load();
+            let entry = Entry::unpack(data);
+            if k == key {
+                replace = slot;
+                if best_move.is_none() {
+                    best_move = entry.and_then(|e| e.best_move);
+                }
+                break;
+            }
+            // Empty slots first, and then shallower and older entries
+            let value = entry.map_or(i32::MIN, |e| {
+                let age = generation.wrapping_sub(e.generation) & GENERATION_MASK;
+                i32::from(e.depth) - 8 * i32::from(age)
+            });
+            if value < replace_value {
+                replace = slot;
+                replace_value = value;
+            }
+        }
+        let entry = Entry {
+            best_move,
+            score,
+            depth,
+            bound,
+            generation,
+        };
+        replace.store(key, entry.pack());
+    }
+    /// Permille of the entries used by the current search, estimated from the first 1000 entries.
+    pub fn hashfull(&self) -> usize {
+        let generation = self.generation();
+        let slots = self
+            .buckets
+            .iter()
+            .flat_map(|bucket| &bucket.0)
+            .take(1000)
+            .collect::<Vec<_>>();
+        let used = slots
+            .iter()
+            .filter(|slot| {
+                matches!(Entry::unpack(slot.load().1), Some(e) if e.generation == generation)
+            })
+            .count();
+        used * 1000 / slots.len()
+    }
+    fn bucket(&self, key: u64) -> &Bucket {
+        let index = (u128::from(key) * self.buckets.len() as u128) >> 64;
+        &self.buckets[index as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn moves() -> Vec<Move> {
+        vec![
+            Move::Normal {
+                from: Square::SQ_7G,
+                to: Square::SQ_7F,
+                promote: false,
+            },
+            Move::Normal {
+                from: Square::SQ_8H,
+                to: Square::SQ_2B,
+                promote: true,
+            },
+            Move::Drop {
+                to: Square::SQ_5E,
+                piece: Piece::W_B,
+            },
+        ]
+    }
+
+    #[test]
+    fn encode_decode_move() {
+        for m in moves() {
+            assert_eq!(Some(m), decode_move(encode_move(m)));
+        }
+        assert_eq!(None, decode_move(0));
+    }
+
+    #[test]
+    fn probe_store() {
+        let table = TranspositionTable::new(1);
+        assert_eq!((1 << 20) / 64 * 4, table.capacity());
+        assert_eq!(None, table.probe(1));
+        let m = moves()[1];
+        table.store(1, Some(m), -123, 5, Bound::Lower);
+        assert_eq!(
+            Some(Entry {
+                best_move: Some(m),
+                score: -123,
+                depth: 5,
+                bound: Bound::Lower,
+                generation: 0,
+            }),
+            table.probe(1)
+        );
+        assert_eq!(None, table.probe(2));
+
+        // The best move is kept if not given
+        table.new_search();
+        table.store(1, None, 30000, 7, Bound::Exact);
+        assert_eq!(
+            Some(Entry {
+                best_move: Some(m),
+                score: 30000,
+                depth: 7,
+                bound: Bound::Exact,
+                generation: 1,
+            }),
+            table.probe(1)
+        );
+
+        table.clear();
+        assert_eq!(None, table.probe(1));
+    }
+
+    #[test]
+    fn replace() {
+        // A single bucket
+        let table = TranspositionTable::new(0);
+        assert_eq!(BUCKET_SIZE, table.capacity());
+        for key in 1..=4 {
+            table.store(key, None, 0, key as u8, Bound::Exact);
+        }
+        // The shallowest entry is replaced
+        table.store(5, None, 0, 3, Bound::Exact);
+        assert_eq!(None, table.probe(1));
+        assert!((2..=5).all(|key| table.probe(key).is_some()));
+        // Older entries are replaced first
+        table.new_search();
+        table.store(6, None, 0, 1, Bound::Exact);
+        table.store(7, None, 0, 1, Bound::Exact);
+        assert_eq!(None, table.probe(2));
+        assert_eq!(None, table.probe(5));
+        assert!([3, 4, 6, 7].iter().all(|&key| table.probe(key).is_some()));
+    }
+
+    #[test]
+    fn hashfull() {
+        let mut table = TranspositionTable::new(1);
+        assert_eq!(0, table.hashfull());
+        for i in 0..100_000_u64 {
+            table.store(
+                i.wrapping_mul(0x9e37_79b9_7f4a_7c15),
+                None,
+                0,
+                1,
+                Bound::Exact,
+            );
+        }
+        assert!(table.hashfull() > 500);
+        table.new_search();
+        assert_eq!(0, table.hashfull());
+
+        table.resize(2);
+        assert_eq!((2 << 20) / 64 * 4, table.capacity());
+        assert_eq!(0, table.hashfull());
+    }
+
+    #[test]
+    fn concurrent() {
+        let table = Arc::new(TranspositionTable::new(0));
+        // Data derived from the key, to detect entries mixed by racing stores
+        let score = |key: u64| (key % 1000) as i16;
+        std::thread::scope(|s| {
+            for t in 0..4_u64 {
+                let table = Arc::clone(&table);
+                s.spawn(move || {
+                    for i in 0..10_000_u64 {
+                        let key = (i % 16) * 4 + t + 1;
+                        table.store(key, None, score(key), (key % 64) as u8, Bound::Exact);
+                        if let Some(entry) = table.probe(key ^ 1) {
+                            assert_eq!(score(key ^ 1), entry.score);
+                            assert_eq!(((key ^ 1) % 64) as u8, entry.depth);
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
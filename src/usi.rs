@@ -0,0 +1,704 @@
+//! USI (Universal Shogi Interface) protocol front-end, with a pluggable search backend.
+
+use crate::search::{is_mate_score, Evaluator, Limits, SearchResult, Searcher, MATE};
+use crate::tsume::{Solver, TsumeResult};
+use crate::Position;
+use shogi_core::{Color, ToUsi};
+use shogi_usi_parser::FromUsi;
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Time kept for the communication with the GUI, subtracted from the time to think.
+const MARGIN: Duration = Duration::from_millis(100);
+const MIN_THINKING_TIME: Duration = Duration::from_millis(10);
+/// Expected number of the remaining moves, to divide the remaining time by.
+const MOVES_TO_GO: u32 = 40;
+
+/// Search engine driven by [`Usi`].
+pub trait Backend: Send + 'static {
+    /// Name of the engine, for `id name`.
+    fn name(&self) -> String;
+    /// Author of the engine, for `id author`.
+    fn author(&self) -> String;
+    /// Options of the engine, each as the part after `option ` of the `option` command.
+    fn options(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Sets the option by `setoption`. `USI_Ponder` is handled by [`Usi`] and not passed.
+    fn set_option(&mut self, _name: &str, _value: Option<&str>) {}
+    /// Prepares for `isready`, for example by allocating tables.
+    fn prepare(&mut self) {}
+    /// Starts a new game by `usinewgame`.
+    fn new_game(&mut self) {}
+    /// Flag to stop the search, set by `stop`, `quit`, `gameover` and the time limit.
+    /// It's cleared before each search.
+    fn stop_flag(&self) -> Arc<AtomicBool>;
+    /// Searches the position within the limits, calling `on_iteration` with each intermediate result.
+    fn search(
+        &mut self,
+        pos: &mut Position,
+        limits: &Limits,
+        on_iteration: &mut dyn FnMut(&SearchResult),
+    ) -> SearchResult;
+}
+
+impl<E: Evaluator + Send + 'static> Backend for Searcher<E> {
+    fn name(&self) -> String {
+        format!("yasai {}", env!("CARGO_PKG_VERSION"))
+    }
+    fn author(&self) -> String {
+        String::from("sugyan")
+    }
+    fn options(&self) -> Vec<String> {
+        vec![String::from(
+            "name USI_Hash type spin default 16 min 1 max 65536",
+        )]
+    }
+    fn set_option(&mut self, name: &str, value: Option<&str>) {
+        if name == "USI_Hash" {
+            if let Some(mb) = value.and_then(|v| v.parse().ok()) {
+                self.resize_table(mb);
+            }
+        }
+    }
+    fn new_game(&mut self) {
+        self.clear();
+    }
+    fn stop_flag(&self) -> Arc<AtomicBool> {
+        Searcher::stop_flag(self)
+    }
+    fn search(
+        &mut self,
+        pos: &mut Position,
+        limits: &Limits,
+        on_iteration: &mut dyn FnMut(&SearchResult),
+    ) -> SearchResult {
+        self.search_with(pos, limits, on_iteration)
+    }
+}
+
+/// Parameters of the `go` command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Go {
+    ponder: bool,
+    time: [u64; Color::NUM],
+    inc: [u64; Color::NUM],
+    byoyomi: u64,
+    /// Set if any of the times is given, even if all of them are 0.
+    timed: bool,
+    infinite: bool,
+    /// Time limit of `go mate` in milliseconds, `None` for `infinite`.
+    mate: Option<Option<u64>>,
+    depth: Option<u32>,
+    nodes: Option<u64>,
+}
+
+impl Go {
+    fn parse(args: &[&str]) -> Go {
+        let mut go = Go::default();
+        let mut iter = args.iter();
+        let next_u64 = |iter: &mut std::slice::Iter<&str>| {
+            iter.next().and_then(|s| s.parse::<u64>().ok()).unwrap_or(0)
+        };
+        while let Some(&arg) = iter.next() {
+            go.timed |= matches!(arg, "btime" | "wtime" | "binc" | "winc" | "byoyomi");
+            match arg {
+                "ponder" => go.ponder = true,
+                "btime" => go.time[Color::Black.array_index()] = next_u64(&mut iter),
+                "wtime" => go.time[Color::White.array_index()] = next_u64(&mut iter),
+                "binc" => go.inc[Color::Black.array_index()] = next_u64(&mut iter),
+                "winc" => go.inc[Color::White.array_index()] = next_u64(&mut iter),
+                "byoyomi" => go.byoyomi = next_u64(&mut iter),
+                "infinite" => go.infinite = true,
+                "mate" => {
+                    go.mate = Some(match iter.next() {
+                        Some(&"infinite") | None => None,
+                        Some(s) => s.parse().ok(),
+                    })
+                }
+                "depth" => go.depth = Some(next_u64(&mut iter) as u32),
+                "nodes" => go.nodes = Some(next_u64(&mut iter)),
+                _ => {}
+            }
+        }
+        go
+    }
+    /// Time to think for the player, or `None` if no time is given.
+    /// With no time left, the minimum time is given rather than thinking infinitely.
+    fn thinking_time(&self, c: Color) -> Option<Duration> {
+        if !self.timed {
+            return None;
+        }
+        let (time, inc) = (self.time[c.array_index()], self.inc[c.array_index()]);
+        let available = Duration::from_millis(time + self.byoyomi).saturating_sub(MARGIN);
+        let planned = Duration::from_millis(time) / MOVES_TO_GO
+            + Duration::from_millis(inc + self.byoyomi).saturating_sub(MARGIN);
+        Some(planned.min(available).max(MIN_THINKING_TIME))
+    }
+}
+
+/// State of the running search shared with its threads, notified by [`Signal::update`].
+#[derive(Debug, Default)]
+struct SignalState {
+    /// Set while `bestmove` must be held back: pondering or searching infinitely.
+    hold: bool,
+    /// Set when the search is stopped or finished, which also cancels the timer.
+    stopped: bool,
+}
+
+#[derive(Debug, Default)]
+struct Signal {
+    state: Mutex<SignalState>,
+    changed: Condvar,
+}
+
+impl Signal {
+    fn update(&self, f: impl FnOnce(&mut SignalState)) {
+        f(&mut self.state.lock().unwrap());
+        self.changed.notify_all();
+    }
+}
+
+/// USI protocol loop, reading commands and writing responses line by line.
+///
+/// The search runs in another thread, so that `stop` and `ponderhit` are handled during the search.
+pub struct Usi<B: Backend> {
+    backend: Arc<Mutex<B>>,
+    /// Response to `usi`, taken before the backend is locked by searches.
+    usi_lines: Vec<String>,
+    /// Options set during the search, applied after it.
+    pending_options: Vec<(String, Option<String>)>,
+    stop: Arc<AtomicBool>,
+    signal: Arc<Signal>,
+    mate_stop: Option<Arc<AtomicBool>>,
+    /// Set if the running search doesn't end without `stop`.
+    unbounded: bool,
+    ponder_time: Option<Duration>,
+    position: Position,
+    ponder: bool,
+    handle: Option<JoinHandle<()>>,
+    /// Timer started by `ponderhit` to stop the search.
+    timer: Option<JoinHandle<()>>,
+}
+
+impl<B: Backend> Usi<B> {
+    pub fn new(backend: B) -> Self {
+        let stop = backend.stop_flag();
+        let mut usi_lines = vec![
+            format!("id name {}", backend.name()),
+            format!("id author {}", backend.author()),
+            String::from("option name USI_Ponder type check default false"),
+        ];
+        usi_lines.extend(backend.options().iter().map(|o| format!("option {o}")));
+        usi_lines.push(String::from("usiok"));
+        Self {
+            backend: Arc::new(Mutex::new(backend)),
+            usi_lines,
+            pending_options: Vec::new(),
+            stop,
+            signal: Arc::new(Signal::default()),
+            mate_stop: None,
+            unbounded: false,
+            ponder_time: None,
+            position: Position::default(),
+            ponder: false,
+            handle: None,
+            timer: None,
+        }
+    }
+    /// Runs the loop until `quit` or the end of the input, and returns the output.
+    /// At the end of the input, the running search is waited for, or stopped if it has no limit.
+    pub fn run<R, W>(&mut self, input: R, output: W) -> io::Result<W>
+    where
+        R: BufRead,
+        W: Write + Send + 'static,
+    {
+        let output = Arc::new(Mutex::new(output));
+        for line in input.lines() {
+            let line = line?;
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            match tokens.as_slice() {
+                ["usi"] => write_lines(&output, &self.usi_lines)?,
+                ["isready"] => {
+                    self.stop();
+                    self.backend.lock().unwrap().prepare();
+                    write_lines(&output, &["readyok"])?;
+                }
+                ["setoption", "name", name, rest @ ..] => {
+                    let value = match rest {
+                        ["value", value @ ..] => Some(value.join(" ")),
+                        _ => None,
+                    };
+                    if *name == "USI_Ponder" {
+                        self.ponder = value.as_deref() == Some("true");
+                    } else {
+                        self.pending_options.push((name.to_string(), value));
+                        if !self.is_searching() {
+                            self.wait();
+                        }
+                    }
+                }
+                ["usinewgame"] => {
+                    self.stop();
+                    self.backend.lock().unwrap().new_game();
+                }
+                ["position", ..] => match parse_position(&line["position".len()..]) {
+                    Ok(pos) => self.position = pos,
+                    Err(e) => write_lines(&output, &[format!("info string {e}")])?,
+                },
+                ["go", args @ ..] => self.go(&Go::parse(args), &output),
+                ["stop"] => self.stop(),
+                ["ponderhit"] => self.ponderhit(),
+                ["gameover", ..] => self.stop(),
+                ["quit"] => {
+                    self.stop();
+                    break;
+                }
+                _ => {}
+            }
+        }
+        if self.unbounded {
+            self.stop();
+        } else {
+            self.wait();
+        }
+        Ok(Arc::try_unwrap(output)
+            .ok()
+            .expect("output is still shared")
+            .into_inner()
+            .unwrap())
+    }
+    fn go<W: Write + Send + 'static>(&mut self, go: &Go, output: &Arc<Mutex<W>>) {
+        self.stop();
+        self.stop.store(false, Ordering::SeqCst);
+        self.signal.update(|s| {
+            s.hold = go.ponder || go.infinite;
+            s.stopped = false;
+        });
+        let mut pos = self.position.clone();
+        let output = Arc::clone(output);
+        if let Some(time_limit) = go.mate {
+            let mut solver = Solver::new(None, time_limit.map(Duration::from_millis));
+            self.unbounded = time_limit.is_none();
+            self.mate_stop = Some(solver.stop_flag());
+            self.handle = Some(thread::spawn(move || {
+                let line = match solver.solve(&mut pos) {
+                    TsumeResult::Mate(pv) => format!("checkmate {}", usi_moves(&pv)),
+                    TsumeResult::NoMate => String::from("checkmate nomate"),
+                    TsumeResult::Unknown => String::from("checkmate timeout"),
+                };
+                let _ = write_lines(&output, &[line]);
+            }));
+            return;
+        }
+        let thinking_time = go.thinking_time(pos.side_to_move());
+        let limits = Limits {
+            depth: go.depth,
+            nodes: go.nodes,
+            time: if go.ponder || go.infinite {
+                None
+            } else {
+                thinking_time
+            },
+        };
+        self.unbounded = go.ponder
+            || go.infinite
+            || (limits.depth.is_none() && limits.nodes.is_none() && limits.time.is_none());
+        self.ponder_time = if go.ponder { thinking_time } else { None };
+        let backend = Arc::clone(&self.backend);
+        let (signal, ponder) = (Arc::clone(&self.signal), self.ponder);
+        self.handle = Some(thread::spawn(move || {
+            let start = Instant::now();
+            let mut backend = backend.lock().unwrap();
+            let result = backend.search(&mut pos, &limits, &mut |result| {
+                let _ = write_lines(&output, &[info(result, start.elapsed())]);
+            });
+            // `bestmove` must not be sent until `stop` or `ponderhit` while pondering or searching infinitely.
+            drop(
+                signal
+                    .changed
+                    .wait_while(signal.state.lock().unwrap(), |s| s.hold && !s.stopped)
+                    .unwrap(),
+            );
+            let mut line = format!(
+                "bestmove {}",
+                result
+                    .best_move
+                    .map_or(String::from("resign"), |m| m.to_usi_owned())
+            );
+            if let (true, Some(m)) = (ponder, result.pv.get(1)) {
+                line += &format!(" ponder {}", m.to_usi_owned());
+            }
+            let _ = write_lines(&output, &[line]);
+        }));
+    }
+    /// Stops the running search and waits for it.
+    fn stop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(mate_stop) = self.mate_stop.take() {
+            mate_stop.store(true, Ordering::SeqCst);
+        }
+        self.signal.update(|s| s.stopped = true);
+        self.wait();
+    }
+    /// Switches the pondering search to the normal one, with the time to think given by the `go ponder`.
+    fn ponderhit(&mut self) {
+        self.signal.update(|s| s.hold = false);
+        if let Some(time) = self.ponder_time.take() {
+            let (stop, signal) = (Arc::clone(&self.stop), Arc::clone(&self.signal));
+            self.timer = Some(thread::spawn(move || {
+                let (state, timeout) = signal
+                    .changed
+                    .wait_timeout_while(signal.state.lock().unwrap(), time, |s| !s.stopped)
+                    .unwrap();
+                drop(state);
+                if timeout.timed_out() {
+                    stop.store(true, Ordering::SeqCst);
+                }
+            }));
+        }
+    }
+    fn is_searching(&self) -> bool {
+        matches!(&self.handle, Some(handle) if !handle.is_finished())
+    }
+    /// Waits for the running search to finish, cancels its timer, and then applies the pending options.
+    fn wait(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("search thread panicked");
+        }
+        self.signal.update(|s| s.stopped = true);
+        if let Some(timer) = self.timer.take() {
+            timer.join().expect("timer thread panicked");
+        }
+        self.mate_stop = None;
+        if !self.pending_options.is_empty() {
+            let mut backend = self.backend.lock().unwrap();
+            for (name, value) in self.pending_options.drain(..) {
+                backend.set_option(&name, value.as_deref());
+            }
+        }
+    }
+}
+
+/// Parses the arguments of the `position` command: `startpos` or `sfen ...`, followed by optional `moves ...`.
+fn parse_position(args: &str) -> Result<Position, String> {
+    let core = shogi_core::Position::from_usi(args.trim()).map_err(|e| format!("{e:?}"))?;
+    Position::from_core_position(&core).map_err(|e| e.to_string())
+}
+
+fn usi_moves(moves: &[shogi_core::Move]) -> String {
+    moves
+        .iter()
+        .map(|m| m.to_usi_owned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn info(result: &SearchResult, elapsed: Duration) -> String {
+    let score = if is_mate_score(result.score) {
+        let plies = MATE - result.score.abs();
+        if result.score > 0 {
+            format!("mate {plies}")
+        } else {
+            format!("mate -{plies}")
+        }
+    } else {
+        format!("cp {}", result.score)
+    };
+    let millis = elapsed.as_millis();
+    let nps = u128::from(result.nodes) * 1000 / millis.max(1);
+    format!(
+        "info depth {} score {score} nodes {} nps {nps} time {millis} pv {}",
+        result.depth,
+        result.nodes,
+        usi_moves(&result.pv)
+    )
+}
+
+fn write_lines<W: Write, S: AsRef<str>>(output: &Mutex<W>, lines: &[S]) -> io::Result<()> {
+    let mut output = output.lock().unwrap();
+    for line in lines {
+        writeln!(output, "{}", line.as_ref())?;
+    }
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MaterialPst;
+    use std::io::{BufReader, Cursor, Read};
+    use std::sync::mpsc::{self, Receiver, Sender};
+
+    fn run(script: &str) -> Vec<String> {
+        let mut usi = Usi::new(Searcher::new(MaterialPst::default(), 1));
+        let output = usi
+            .run(Cursor::new(script.to_string()), Vec::new())
+            .expect("failed to run");
+        String::from_utf8(output)
+            .expect("invalid output")
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Input of the engine fed line by line from the test.
+    struct ChannelReader {
+        lines: Receiver<String>,
+        buf: Vec<u8>,
+        pos: usize,
+    }
+
+    impl Read for ChannelReader {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            if self.pos == self.buf.len() {
+                match self.lines.recv() {
+                    Ok(line) => self.buf = format!("{line}\n").into_bytes(),
+                    Err(_) => return Ok(0),
+                }
+                self.pos = 0;
+            }
+            let len = out.len().min(self.buf.len() - self.pos);
+            out[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+            self.pos += len;
+            Ok(len)
+        }
+    }
+
+    /// Output of the engine sent to the test line by line.
+    struct ChannelWriter {
+        lines: Sender<String>,
+        buf: Vec<u8>,
+    }
+
+    impl Write for ChannelWriter {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            self.buf.extend_from_slice(data);
+            while let Some(i) = self.buf.iter().position(|&b| b == b'\n') {
+                let line = self.buf.drain(..=i).collect::<Vec<_>>();
+                let line = String::from_utf8_lossy(&line[..i]).into_owned();
+                let _ = self.lines.send(line);
+            }
+            Ok(data.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Engine running in another thread, to send commands while it's searching.
+    struct Session {
+        input: Option<Sender<String>>,
+        output: Receiver<String>,
+        handle: Option<JoinHandle<()>>,
+    }
+
+    impl Session {
+        fn new() -> Self {
+            let (input, lines) = mpsc::channel();
+            let (sender, output) = mpsc::channel();
+            let handle = thread::spawn(move || {
+                let reader = ChannelReader {
+                    lines,
+                    buf: Vec::new(),
+                    pos: 0,
+                };
+                let writer = ChannelWriter {
+                    lines: sender,
+                    buf: Vec::new(),
+                };
+                let mut usi = Usi::new(Searcher::new(MaterialPst::default(), 1));
+                usi.run(BufReader::new(reader), writer)
+                    .expect("failed to run");
+            });
+            Self {
+                input: Some(input),
+                output,
+                handle: Some(handle),
+            }
+        }
+        fn send(&self, line: &str) {
+            self.input
+                .as_ref()
+                .unwrap()
+                .send(line.to_string())
+                .expect("engine exited");
+        }
+        /// Receives the lines until the one starting with `prefix`, which is the last of them.
+        fn expect(&self, prefix: &str) -> Vec<String> {
+            let mut lines = Vec::new();
+            loop {
+                let line = self.output.recv().expect("engine exited");
+                let found = line.starts_with(prefix);
+                lines.push(line);
+                if found {
+                    return lines;
+                }
+            }
+        }
+    }
+
+    impl Drop for Session {
+        fn drop(&mut self) {
+            // The end of the input makes the engine exit.
+            self.input = None;
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    #[test]
+    fn handshake() {
+        let lines = run("usi\nsetoption name USI_Hash value 4\nisready\nusinewgame\nquit\n");
+        assert!(lines[0].starts_with("id name yasai"));
+        assert!(lines[1].starts_with("id author"));
+        assert!(lines.contains(&String::from(
+            "option name USI_Ponder type check default false"
+        )));
+        assert_eq!(vec!["usiok", "readyok"], lines[lines.len() - 2..].to_vec());
+    }
+
+    #[test]
+    fn usi_and_setoption_while_searching() {
+        let mut usi = Usi::new(Searcher::new(MaterialPst::default(), 1));
+        let script = "position startpos\ngo infinite\nusi\nsetoption name USI_Hash value 2\nstop\n";
+        let output = usi
+            .run(Cursor::new(script.to_string()), Vec::new())
+            .expect("failed to run");
+        let lines = String::from_utf8(output).expect("invalid output");
+        let usiok = lines.find("usiok").expect("no usiok");
+        assert!(usiok < lines.find("bestmove").expect("no bestmove"));
+        // The option is applied after the search
+        let capacity = usi.backend.lock().unwrap().table().capacity();
+        assert_eq!(
+            Searcher::new(MaterialPst::default(), 2).table().capacity(),
+            capacity
+        );
+    }
+
+    #[test]
+    fn go() {
+        let lines = run("position startpos moves 7g7f 3c3d\ngo depth 2\n");
+        assert!(lines[0].starts_with("info depth 1 score cp "));
+        assert!(lines[1].starts_with("info depth 2 score cp "));
+        assert!(lines[2].starts_with("bestmove "));
+        assert_eq!(3, lines.len());
+
+        // Mate in one
+        let lines = run(
+            "position sfen 4k4/9/4P4/9/9/9/9/9/4K4 b G2r2b3g4s4n4l17p 1\ngo btime 0 wtime 0 byoyomi 1000\n",
+        );
+        assert!(lines[0].starts_with("info depth 1 score mate 1 "));
+        assert_eq!("bestmove G*5b", lines[lines.len() - 1]);
+
+        // Checkmated
+        let lines =
+            run("position sfen 4k4/4G4/4P4/9/9/9/9/9/4K4 w 2r2b3g4s4n4l17p 2\ngo nodes 100\n");
+        assert_eq!(vec!["bestmove resign"], lines);
+    }
+
+    #[test]
+    fn stop_and_ponderhit() {
+        let session = Session::new();
+        session.send("setoption name USI_Ponder value true");
+        session.send("position startpos");
+        session.send("go infinite");
+        session.expect("info depth 1 ");
+        session.send("stop");
+        session.expect("bestmove ");
+
+        // `bestmove` is held back until `ponderhit`, even after the search ends
+        session.send("go ponder depth 2");
+        session.expect("info depth 2 ");
+        session.send("ponderhit");
+        let lines = session.expect("bestmove ");
+        assert_eq!(1, lines.len());
+        assert!(lines[0].contains(" ponder "));
+
+        // The time to think given by `go ponder` starts at `ponderhit`
+        session.send("go ponder btime 0 wtime 0 byoyomi 200");
+        session.expect("info depth 1 ");
+        session.send("ponderhit");
+        session.expect("bestmove ");
+
+        // Stopped by `isready` and `usinewgame`
+        session.send("go infinite");
+        session.expect("info depth 1 ");
+        session.send("isready");
+        let lines = session.expect("readyok");
+        assert!(lines[lines.len() - 2].starts_with("bestmove "));
+        session.send("go infinite");
+        session.expect("info depth 1 ");
+        session.send("usinewgame");
+        session.send("usi");
+        let lines = session.expect("usiok");
+        assert!(lines.iter().any(|line| line.starts_with("bestmove ")));
+    }
+
+    #[test]
+    fn end_of_input() {
+        let lines = run("position startpos\ngo infinite\n");
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+        let lines = run("setoption name USI_Ponder value true\nposition startpos\ngo ponder btime 0 wtime 0 byoyomi 200\n");
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn mate() {
+        let lines =
+            run("position sfen 4k4/9/4P4/9/9/9/9/9/4K4 b G2r2b3g4s4n4l17p 1\ngo mate 1000\n");
+        assert_eq!(vec!["checkmate G*5b"], lines);
+        let lines = run("position startpos\ngo mate infinite\n");
+        assert_eq!(vec!["checkmate nomate"], lines);
+
+        // Stopped while finding the shortest mate
+        let session = Session::new();
+        session.send("position sfen 9/7k1/9/9/9/9/9/9/9 b 2R2B4G4S4N4L18P 1");
+        session.send("go mate infinite");
+        session.send("stop");
+        assert_eq!(1, session.expect("checkmate ").len());
+    }
+
+    #[test]
+    fn invalid_position() {
+        let lines = run("position startpos moves 7g7f 3c3d 2h2d\nquit\n");
+        assert_eq!(1, lines.len());
+        assert!(lines[0].starts_with("info string "));
+    }
+
+    #[test]
+    fn thinking_time() {
+        let go = Go::parse(&["btime", "60000", "wtime", "1000", "byoyomi", "3000"]);
+        assert_eq!(
+            Some(Duration::from_millis(60000 / 40 + 3000 - 100)),
+            go.thinking_time(Color::Black)
+        );
+        assert_eq!(
+            Some(Duration::from_millis(1000 / 40 + 3000 - 100)),
+            go.thinking_time(Color::White)
+        );
+        let go = Go::parse(&[
+            "btime", "500", "wtime", "500", "binc", "1000", "winc", "1000",
+        ]);
+        assert_eq!(
+            Some(Duration::from_millis(400)),
+            go.thinking_time(Color::Black)
+        );
+        assert_eq!(None, Go::parse(&["depth", "5"]).thinking_time(Color::Black));
+        assert_eq!(
+            Some(MIN_THINKING_TIME),
+            Go::parse(&["btime", "0", "wtime", "0", "byoyomi", "0"]).thinking_time(Color::Black)
+        );
+        assert_eq!(
+            Go {
+                mate: Some(None),
+                ..Default::default()
+            },
+            Go::parse(&["mate", "infinite"])
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use crate::bitboard::Bitboard;
+use crate::movegen::is_movable_rank;
+use crate::tables::ATTACK_TABLE;
+use crate::Position;
+use shogi_core::{Color, Hand, PartialPosition, Piece, PieceKind, Square, ToUsi};
+use std::fmt;
+
+/// Represents a problem of a position found by [`Position::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    /// The player has no king
+    MissingKing(Color),
+    /// The player has two or more kings, at the squares
+    MultipleKings(Color, Vec<Square>),
+    /// The piece at the square can't move anymore (行き所のない駒)
+    DeadPiece(Square),
+    /// The player has two or more unpromoted pawns on the same file (二歩), at the squares
+    DoublePawns(Color, Vec<Square>),
+    /// The king of the side not to move can be captured by the pieces at the squares
+    OpponentInCheck(Vec<Square>),
+    /// More pieces of the kind than the game has, counting promoted ones and pieces in hand
+    TooManyPieces(PieceKind, u8),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let squares = |squares: &[Square]| {
+            squares
+                .iter()
+                .map(|sq| sq.to_usi_owned())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            PositionError::MissingKing(c) => write!(f, "{c:?} has no king"),
+            PositionError::MultipleKings(c, sqs) => {
+                write!(f, "{c:?} has multiple kings at {}", squares(sqs))
+            }
+            PositionError::DeadPiece(sq) => {
+                write!(f, "the piece at {} can't move", sq.to_usi_owned())
+            }
+            PositionError::DoublePawns(c, sqs) => {
+                write!(f, "{c:?} has double pawns at {}", squares(sqs))
+            }
+            PositionError::OpponentInCheck(sqs) => {
+                write!(f, "the side not to move is in check by {}", squares(sqs))
+            }
+            PositionError::TooManyPieces(pk, num) => write!(f, "too many {pk:?}s: {num}"),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+impl Position {
+    /// Validates the position for a normal game, where both players must have a king.
+    ///
+    /// All the problems found are reported.
+    pub fn validate(&self) -> Result<(), Vec<PositionError>> {
+        validate(&self.to_partial_position(), &Color::all())
+    }
+    /// Validates the position for tsume shogi (詰将棋), where Black, the attacker, may have no king.
+    pub fn validate_tsume(&self) -> Result<(), Vec<PositionError>> {
+        validate(&self.to_partial_position(), &[Color::White])
+    }
+}
+
+impl TryFrom<PartialPosition> for Position {
+    type Error = Vec<PositionError>;
+
+    /// Creates a position for a normal game, validated as [`Position::validate`].
+    fn try_from(partial: PartialPosition) -> Result<Self, Self::Error> {
+        validate(&partial, &Color::all())?;
+        Ok(Position::new(partial))
+    }
+}
+
+/// Validates the position before creating a [`Position`], which assumes a valid position.
+fn validate(partial: &PartialPosition, required_kings: &[Color]) -> Result<(), Vec<PositionError>> {
+    let mut errors = Vec::new();
+    let pieces = Square::all()
+        .filter_map(|sq| partial.piece_at(sq).map(|p| (sq, p)))
+        .collect::<Vec<_>>();
+    let kings = |c: Color| {
+        pieces
+            .iter()
+            .filter(|(_, p)| *p == Piece::new(PieceKind::King, c))
+            .map(|&(sq, _)| sq)
+            .collect::<Vec<_>>()
+    };
+    for c in Color::all() {
+        let kings = kings(c);
+        match kings.len() {
+            0 if required_kings.contains(&c) => errors.push(PositionError::MissingKing(c)),
+            0 | 1 => {}
+            _ => errors.push(PositionError::MultipleKings(c, kings)),
+        }
+    }
+    // 駒数
+    let mut counts = [0_u8; PieceKind::NUM];
+    for (_, p) in &pieces {
+        let pk = p.piece_kind();
+        counts[pk.unpromote().unwrap_or(pk).array_index()] += 1;
+    }
+    for c in Color::all() {
+        let hand = partial.hand_of_a_player(c);
+        for pk in Hand::all_hand_pieces() {
+            counts[pk.array_index()] += hand.count(pk).unwrap_or_default();
+        }
+    }
+    for (pk, max) in [
+        (PieceKind::Pawn, 18),
+        (PieceKind::Lance, 4),
+        (PieceKind::Knight, 4),
+        (PieceKind::Silver, 4),
+        (PieceKind::Gold, 4),
+        (PieceKind::Bishop, 2),
+        (PieceKind::Rook, 2),
+    ] {
+        if counts[pk.array_index()] > max {
+            errors.push(PositionError::TooManyPieces(pk, counts[pk.array_index()]));
+        }
+    }
+    // 行き所のない駒
+    for &(sq, p) in &pieces {
+        if !is_movable_rank(p.piece_kind(), sq, p.color()) {
+            errors.push(PositionError::DeadPiece(sq));
+        }
+    }
+    // 二歩
+    for c in Color::all() {
+        for file in 1..=9 {
+            let pawns = pieces
+                .iter()
+                .filter(|(sq, p)| sq.file() == file && *p == Piece::new(PieceKind::Pawn, c))
+                .map(|&(sq, _)| sq)
+                .collect::<Vec<_>>();
+            if pawns.len() > 1 {
+                errors.push(PositionError::DoublePawns(c, pawns));
+            }
+        }
+    }
+    // 手番でない側の王手放置
+    let c = partial.side_to_move();
+    let occ = pieces.iter().fold(Bitboard::empty(), |acc, &(sq, _)| {
+        acc | Bitboard::single(sq)
+    });
+    let opponent_kings = kings(c.flip());
+    let checkers = pieces
+        .iter()
+        .filter(|(sq, p)| {
+            p.color() == c
+                && opponent_kings.iter().any(|&king| {
+                    ATTACK_TABLE
+                        .attack(p.piece_kind(), *sq, c, &occ)
+                        .contains(king)
+                })
+        })
+        .map(|&(sq, _)| sq)
+        .collect::<Vec<_>>();
+    if !checkers.is_empty() {
+        errors.push(PositionError::OpponentInCheck(checkers));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::consts::square::*;
+    use shogi_usi_parser::FromUsi;
+
+    fn validate(sfen: &str) -> Result<(), Vec<PositionError>> {
+        Position::try_from(PartialPosition::from_usi(sfen).expect("failed to parse")).map(|_| ())
+    }
+
+    #[test]
+    fn validate_valid() {
+        assert_eq!(Ok(()), Position::default().validate());
+        assert_eq!(
+            Ok(()),
+            validate("sfen R8/2K1S1SSk/4B4/9/9/9/9/9/1L1L1L3 b RBGSNLP3g3n17p 1")
+        );
+        // P1 *  *  *  *  *  *  *  *  *
+        // P2 *  *  *  *  *  *  *  *  *
+        // P3 *  *  *  *  *  *  *  *  *
+        // P4 *  *  *  *  *  *  *  *  *
+        // P5 *  *  *  *  *  *  *  *  *
+        // P6 *  *  *  *  *  *  *  *  *
+        // P7 *  *  *  *  *  *  *  *  *
+        // P8 *  *  *  *  *  *  *  *  *
+        // P9+KY+KE *  * +OU *  * +KE+KY
+        // +
+        assert_eq!(Ok(()), validate("sfen 4k4/9/9/9/9/9/9/9/LN2K2NL w - 1"));
+    }
+
+    #[test]
+    fn validate_errors() {
+        let test_cases = [
+            (
+                "sfen 9/9/9/9/9/9/9/9/4K4 b - 1",
+                vec![PositionError::MissingKing(Color::White)],
+            ),
+            (
+                "sfen 4k4/9/9/9/9/9/9/9/3KK4 b - 1",
+                vec![PositionError::MultipleKings(
+                    Color::Black,
+                    vec![SQ_5I, SQ_6I],
+                )],
+            ),
+            (
+                "sfen 4k4/9/9/9/9/9/9/9/4K4 b 19p 1",
+                vec![PositionError::TooManyPieces(PieceKind::Pawn, 19)],
+            ),
+            (
+                "sfen 4k4/9/9/9/9/9/9/9/+R3K4 w 2r 1",
+                vec![PositionError::TooManyPieces(PieceKind::Rook, 3)],
+            ),
+            // 行き所のない駒
+            // P1+FU+KY * -OU *  *  *  *  *
+            // P2+KE *  *  *  *  *  *  *  *
+            // P3 *  *  *  *  *  *  *  *  *
+            // P4 *  *  *  *  *  *  *  *  *
+            // P5 *  *  *  *  *  *  *  *  *
+            // P6 *  *  *  *  *  *  *  *  *
+            // P7 *  *  *  *  *  *  *  *  *
+            // P8 *  *  *  *  *  *  *  * -KE
+            // P9 *  *  *  * +OU *  *  * -FU
+            // +
+            (
+                "sfen PL2k4/N8/9/9/9/9/9/8n/4K3p b - 1",
+                vec![
+                    PositionError::DeadPiece(SQ_1H),
+                    PositionError::DeadPiece(SQ_1I),
+                    PositionError::DeadPiece(SQ_8A),
+                    PositionError::DeadPiece(SQ_9A),
+                    PositionError::DeadPiece(SQ_9B),
+                ],
+            ),
+            // 二歩
+            (
+                "sfen 4k4/4p4/9/4p4/9/4P4/4P4/4P4/4K4 b - 1",
+                vec![
+                    PositionError::DoublePawns(Color::Black, vec![SQ_5F, SQ_5G, SQ_5H]),
+                    PositionError::DoublePawns(Color::White, vec![SQ_5B, SQ_5D]),
+                ],
+            ),
+            // 手番でない側の王手放置
+            (
+                "sfen 4k4/9/9/9/9/9/9/9/4RK3 b - 1",
+                vec![PositionError::OpponentInCheck(vec![SQ_5I])],
+            ),
+            (
+                "sfen 4k4/4G4/9/9/9/9/9/9/4K3B b - 1",
+                vec![PositionError::OpponentInCheck(vec![SQ_5B])],
+            ),
+        ];
+        for (sfen, expected) in test_cases {
+            assert_eq!(Err(expected), validate(sfen), "{sfen}");
+        }
+    }
+
+    #[test]
+    fn validate_tsume() {
+        let pos = Position::new(
+            PartialPosition::from_usi("sfen 4k4/9/4P4/9/9/9/9/9/9 b G2r2b3g4s4n4l17p 1")
+                .expect("failed to parse"),
+        );
+        assert_eq!(Ok(()), pos.validate_tsume());
+        assert_eq!(
+            Err(vec![PositionError::MissingKing(Color::Black)]),
+            pos.validate()
+        );
+        let pos = Position::new(
+            PartialPosition::from_usi("sfen 9/9/4P4/9/9/9/9/9/4K4 b G 1").expect("failed to parse"),
+        );
+        assert_eq!(
+            Err(vec![PositionError::MissingKing(Color::White)]),
+            pos.validate_tsume()
+        );
+    }
+
+    #[test]
+    fn try_from() {
+        assert!(Position::try_from(PartialPosition::startpos()).is_ok());
+        let partial = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/3KK4 b - 1")
+            .expect("failed to parse");
+        assert!(Position::try_from(partial).is_err());
+    }
+}